@@ -0,0 +1,38 @@
+#include <util/crc16.h>
+
+// Versão binária do captador_de_frequencia: as leituras são enviadas em quadros com
+// palavra de sincronização, número de sequência e CRC, lidos com `--protocol binary`.
+//
+// Quadro: a5 5a | sequência (1 byte) | n (1 byte) | n amostras u16 little endian | CRC-16/CCITT u16 little endian
+// O CRC cobre a sequência, n e as amostras.
+
+#define AMOSTRAS_POR_QUADRO 32
+
+int analogPin = A0;  // terminal do meio de um potênciometro conectado ao pino analógico 0
+uint8_t sequencia = 0;
+uint8_t quadro[6 + 2 * AMOSTRAS_POR_QUADRO];
+
+void setup() {
+  Serial.begin(115200);  // configura a porta serial
+}
+
+void loop() {
+  quadro[0] = 0xa5;
+  quadro[1] = 0x5a;
+  quadro[2] = sequencia++;
+  quadro[3] = AMOSTRAS_POR_QUADRO;
+  for (int i = 0; i < AMOSTRAS_POR_QUADRO; i++) {
+    uint16_t val = analogRead(analogPin);  // lê o pino de entrada
+    quadro[4 + 2 * i] = val & 0xff;
+    quadro[5 + 2 * i] = val >> 8;
+  }
+
+  uint16_t crc = 0xffff;
+  for (int i = 2; i < 4 + 2 * AMOSTRAS_POR_QUADRO; i++) {
+    crc = _crc_xmodem_update(crc, quadro[i]);
+  }
+  quadro[4 + 2 * AMOSTRAS_POR_QUADRO] = crc & 0xff;
+  quadro[5 + 2 * AMOSTRAS_POR_QUADRO] = crc >> 8;
+
+  Serial.write(quadro, sizeof(quadro));
+}
@@ -1,5 +1,5 @@
 use piston_window::{EventLoop, PistonWindow, WindowSettings};
-use plotters_piston_eeg::draw_piston_window;
+use plotters_piston_eeg::{draw_piston_window, SerialSource};
 use std::collections::{BTreeMap as Map};
 use plotters::prelude::*;
 use std::io::{self, BufRead, BufReader};
@@ -10,22 +10,24 @@ use std::string::ToString;
 use std::sync::mpsc;
 use std::sync::mpsc::{SendError, SyncSender};
 use std::thread;
-use std::time::Duration;
-use serialport::SerialPort;
 use plotters::prelude::SegmentValue;
 
 const FPS: u32 = 60;
-const PORT_NAME: &str = "/dev/ttyUSB0";
-// const PORT_NAME: String = "/dev/ttyACM0".to_string();
 const LENGTH: usize = 35;
-const BAUD_RATE: u32 = 115200;
 
 fn main() {
 
+    // Ex.: --port /dev/ttyACM0 --baud 115200, ou --config placa.conf
+    let source = SerialSource::from_args(std::env::args().skip(1))
+        .unwrap_or_else(|e| {
+            eprintln!("Configuração inválida da porta serial: {}", e);
+            exit(2);
+        });
+
     let (tx, rx) = mpsc::sync_channel(LENGTH);
 
     thread::spawn(move || {
-        read_port(tx);
+        read_port(source, tx);
     });
 
 
@@ -87,12 +89,12 @@ fn main() {
     }){}
 }
 
-fn read_port(tx: SyncSender<(String, String)>) {
+fn read_port(source: SerialSource, tx: SyncSender<(String, String)>) {
 
-    let port = serialport::new(PORT_NAME, BAUD_RATE)
-        .timeout(Duration::from_millis(10))
-        .open()
-        .expect("Falha ao acessar porta.");
+    let port = source.open().unwrap_or_else(|e| {
+        eprintln!("Falha ao acessar porta: {}", e);
+        exit(1);
+    });
 
     let mut reader = BufReader::new(port);
     loop {
@@ -13,7 +13,7 @@ use piston_window::{EventLoop, PistonWindow, WindowSettings};
 use plotters::chart::{ChartBuilder, LabelAreaPosition};
 use plotters::series::LineSeries;
 use plotters::prelude::{Color, IntoDrawingArea, IntoSegmentedCoord, RED, WHITE};
-use plotters_piston_eeg::{draw_piston_window, PistonBackend};
+use plotters_piston_eeg::{draw_piston_window, PistonBackend, SourceEvent};
 
 
 use easyfft::prelude::*;
@@ -29,16 +29,17 @@ static mut Y_MAX: i32 = 0;
 static mut NORM: f32 = 1.0;
 
 fn main() {
+    let source = frequency_reader::serial_source();
     let (tx, rx) = mpsc::sync_channel(LENGTH);
 
     thread::spawn(move || {
-        frequency_reader::read_port(tx);
+        frequency_reader::read_port(source, tx, Default::default());
     });
 
     display(&rx);
 }
 
-fn display(rx: &Receiver<isize>){
+fn display(rx: &Receiver<SourceEvent<isize>>){
     let mut samples = BoundedVecDeque::with_capacity(LENGTH, LENGTH);
 
     let mut window: PistonWindow = WindowSettings::new("Frequências em Tempo Real", [1280, 720])
@@ -49,8 +50,14 @@ fn display(rx: &Receiver<isize>){
     // window.set_max_fps(FPS as u64);
 
     while let Some(_) = draw_piston_window(&mut window, |b| unsafe {
-        for value in rx.try_iter().take(LENGTH/FREQ_DIVISOR as usize) {
-            samples.push_back(value);
+        for event in rx.try_iter().take(LENGTH/FREQ_DIVISOR as usize) {
+            match event {
+                SourceEvent::Sample(value) => {
+                    samples.push_back(value);
+                }
+                // Recomeça depois de uma desconexão da placa
+                SourceEvent::Gap(_) => samples.clear(),
+            }
         }
 
         if samples.len() != LENGTH {
@@ -1,57 +1,130 @@
 use std::io;
-use std::io::{BufRead, BufReader};
+use std::io::Write;
 use std::process::exit;
-use std::str::FromStr;
-use std::sync::mpsc::{SyncSender};
-use std::time::Duration;
-use serialport::SerialPort;
+use std::sync::mpsc::{SyncSender, TrySendError};
 
+use plotters_piston_eeg::{Discovery, FrameDecoder, LineParser, Protocol, Received, SerialConnection, SerialSource, SourceCounters, SourceEvent, AUTO_PORT};
 
-pub fn read_port(sender: SyncSender<isize>) {
 
-    let port_name = "/dev/ttyUSB0";
-    // let port_name = "/dev/ttyACM0";
-    let baud_rate = 115200;
+// Porta serial configurada pelos argumentos da linha de comando, por exemplo
+// `--port /dev/ttyACM0 --baud 230400` ou `--config placa.conf`.
+// Com `--port auto`, a placa conectada é encontrada pelo VID/PID; se houver mais
+// de uma, o usuário escolhe qual usar.
+pub fn serial_source() -> SerialSource {
+    let source = match SerialSource::from_args(std::env::args().skip(1)) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("Configuração inválida da porta serial: {}", e);
+            exit(2);
+        }
+    };
+    if source.port_name() != AUTO_PORT {
+        return source;
+    }
+
+    let devices = match Discovery::scan() {
+        Ok(Discovery::Found(device)) => {
+            println!("Usando {}", device);
+            return source.port(device.port_name);
+        }
+        Ok(Discovery::Several(devices)) => devices,
+        Ok(Discovery::NotFound) => {
+            eprintln!("Nenhuma placa conhecida conectada. Use --port para indicar a porta.");
+            exit(1);
+        }
+        Err(e) => {
+            eprintln!("Falha ao procurar placas: {}", e);
+            exit(1);
+        }
+    };
+
+    println!("Várias placas conectadas:");
+    // As portas não são abertas aqui: abrir uma porta reinicia o Arduino conectado a ela
+    for (i, device) in devices.iter().enumerate() {
+        println!("  {}) {}", i + 1, device);
+    }
+    print!("Escolha uma: ");
+    io::stdout().flush().ok();
+    let mut choice = String::new();
+    io::stdin().read_line(&mut choice).ok();
+    match choice.trim().parse::<usize>().ok().and_then(|i| devices.get(i.wrapping_sub(1))) {
+        Some(device) => source.port(device.port_name.as_str()),
+        None => {
+            eprintln!("Escolha inválida: {:?}", choice.trim());
+            exit(2);
+        }
+    }
+}
 
-    let port = serialport::new(port_name, baud_rate)
-        .timeout(Duration::from_millis(10))
-        .open();
+// Amostras ilegíveis e descartadas por falta de espaço no canal são contadas em `counters`.
+// Se a placa for desconectada, a leitura espera que ela volte e envia a lacuna ao visualizador.
+pub fn read_port(source: SerialSource, sender: SyncSender<SourceEvent<isize>>, counters: SourceCounters) {
 
-    let mut port = match port {
-        Ok(p) => p,
+    let protocol = source.wire_protocol();
+    let mut connection = match SerialConnection::open(source, counters.clone()) {
+        Ok(c) => c,
         Err(e) => {
-            eprintln!("Falha ao acessar porta: {:?}", e);
+            eprintln!("Falha ao acessar porta: {}", e);
             exit(1);
         }
     };
 
-    let mut reader = BufReader::new(port);
+    let mut buffer = [0u8; 1024];
+    // Linhas corrompidas, comuns logo após o reset do Arduino, são descartadas e contadas
+    let mut parser = LineParser::<isize>::new();
+    // No protocolo binário, quadros corrompidos ou perdidos são detectados pela sequência e pelo CRC
+    let mut decoder = FrameDecoder::new();
     loop{
-        let mut leitura = String::new();
-        match reader.read_line(&mut leitura) {
-            Ok(_) => {
-                leitura.split("\n")
-                    .filter(|x| !x.trim().is_empty())
-                    .map(|x| isize::from_str(x.trim()).unwrap())
-                    .for_each(|x|
-                        {
-                            match sender.send(x){
-                                Ok(_) => {}
-                                Err(e) => {
-                                    eprintln!("Error: {}", e);
-                                    exit(1);
-                                }
-                            };
-                        });
+        let n = match connection.read(&mut buffer) {
+            Received::Bytes(n) => n,
+            Received::TimedOut => continue,
+            Received::Reconnected(gap) => {
+                eprintln!("Placa reconectada em {} após {:.1} s", connection.port_name(), gap.duration.as_secs_f64());
+                // A linha ou o quadro interrompido não continua depois da lacuna
+                parser.reset();
+                decoder.reset();
+                if sender.send(SourceEvent::Gap(gap)).is_err() {
+                    return;
+                }
+                continue;
             }
-            Err(ref e) if e.kind() == io::ErrorKind::TimedOut => (),
-            // Err(ref e) if e.kind() == io::ErrorKind::TimedOut => eprintln!("Reading Timeout!"),
-            Err(ref e)
-                if e.kind() == io::ErrorKind::BrokenPipe => {
-                    eprintln!("{:?}", e);
-                    exit(1);
-            },
-            Err(e) => eprintln!("{:?}", e)
+        };
+
+        let amostras: Vec<isize> = match protocol {
+            Protocol::Text => parser.push(&buffer[..n])
+                .into_iter()
+                .filter_map(|leitura| match leitura {
+                    Ok(x) => Some(x),
+                    Err(_) => {
+                        counters.parse_error();
+                        None
+                    }
+                })
+                .collect(),
+            // Um quadro corrompido só é contado como perdido, pela falha na sequência do quadro
+            // seguinte; bytes descartados e falsas palavras de sincronização não são erros de leitura
+            Protocol::Binary => {
+                decoder.push(&buffer[..n]).into_iter()
+                    .flat_map(|quadro| {
+                        if quadro.lost > 0 {
+                            counters.lost_frames(quadro.lost as u64);
+                        }
+                        quadro.samples
+                    })
+                    .map(|x| x as isize)
+                    .collect()
+            }
+        };
+
+        for x in amostras {
+            counters.received(1);
+            match sender.try_send(SourceEvent::Sample(x)) {
+                Ok(_) => {}
+                // O visualizador está atrasado; a amostra é descartada
+                Err(TrySendError::Full(_)) => counters.dropped(1),
+                // O visualizador foi fechado
+                Err(TrySendError::Disconnected(_)) => return,
+            };
         }
     }
 }
@@ -1,6 +1,10 @@
+use plotters_piston_eeg::SerialDevice;
 use serialport::{available_ports, SerialPortType};
 
+// Com `--probe`, cada porta USB é aberta para saber se está disponível.
+// Abrir a porta reinicia placas como o Arduino Uno.
 fn main() {
+    let probe = std::env::args().skip(1).any(|arg| arg == "--probe");
     match available_ports() {
         Ok(ports) => {
             match ports.len() {
@@ -12,27 +16,31 @@ fn main() {
                 println!("  {}", p.port_name);
                 match p.port_type {
                     SerialPortType::UsbPort(info) => {
+                        let device = SerialDevice::new(p.port_name.clone(), info);
                         println!("    Type: USB");
-                        println!("    VID:{:04x} PID:{:04x}", info.vid, info.pid);
+                        println!("    VID:{:04x} PID:{:04x}", device.vid, device.pid);
                         println!(
                             "     Serial Number: {}",
-                            info.serial_number.as_ref().map_or("", String::as_str)
+                            device.serial_number.as_ref().map_or("", String::as_str)
                         );
                         println!(
                             "      Manufacturer: {}",
-                            info.manufacturer.as_ref().map_or("", String::as_str)
+                            device.manufacturer.as_ref().map_or("", String::as_str)
                         );
                         println!(
                             "           Product: {}",
-                            info.product.as_ref().map_or("", String::as_str)
+                            device.product.as_ref().map_or("", String::as_str)
                         );
-                        #[cfg(feature = "usbportinfo-interface")]
                         println!(
-                            "         Interface: {}",
-                            info.interface
-                                .as_ref()
-                                .map_or("".to_string(), |x| format!("{:02x}", *x))
+                            "             Board: {}",
+                            device.board.map_or("unknown", |board| board.name)
                         );
+                        if probe {
+                            match device.problem() {
+                                None => println!("            Status: available"),
+                                Some(problem) => println!("            Status: {}", problem),
+                            }
+                        }
                     }
                     SerialPortType::BluetoothPort => {
                         println!("    Type: Bluetooth");
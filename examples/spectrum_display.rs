@@ -1,21 +1,24 @@
 #![allow(unused_imports)]
 
 use std::any::Any;
+use std::error::Error;
 use std::iter::zip;
 use std::thread;
 use std::ops::Range;
+use std::time::{Duration, Instant};
 use std::sync::mpsc;
 use std::sync::mpsc::Receiver;
 
 use bounded_vec_deque::BoundedVecDeque;
 
-use piston_window::{EventLoop, PistonWindow, WindowSettings};
+use piston_window::{Button, Event, EventLoop, PistonWindow, PressEvent, RenderEvent, UpdateArgs, WindowSettings};
 
-use plotters::chart::{ChartBuilder, ChartContext, LabelAreaPosition};
-use plotters::series::LineSeries;
-use plotters::element::Rectangle;
-use plotters::prelude::{Cartesian2d, Color, IntoDrawingArea, IntoSegmentedCoord, RED, SegmentValue, WHITE};
-use plotters_piston_eeg::{draw_piston_window, PistonBackend};
+use plotters::chart::{ChartBuilder, ChartContext, ChartState, LabelAreaPosition};
+use plotters::series::{AreaSeries, LineSeries};
+use plotters::element::{PathElement, Rectangle, Text};
+use plotters::prelude::{Cartesian2d, Color, DrawingArea, IntoDrawingArea, IntoFont, IntoSegmentedCoord, RGBColor, BLACK, RED, SegmentValue, TextStyle, WHITE};
+use plotters_piston_eeg::{run_piston_app, save_screenshot, ChartView, Diagnostics, DrawResult, FillGradient, FillGradientHandle, FrameFormat, FrameRecorder, KeyBindings, LinePattern, LoopRates, PistonApp, PistonBackend, SourceEvent};
+use plotters::coord::Shift;
 use plotters::coord::ranged1d::SegmentedCoord;
 use plotters::coord::types::{RangedCoordf32, RangedCoordf64, RangedCoordi32};
 
@@ -40,160 +43,507 @@ Volume ideal: 53% ou -16,3db
 const FREQ_DIVISOR: i32 = 4;
 const LENGTH:usize = 4096*(FREQ_DIVISOR as usize);
 const FPS: u32 = 60;
+// Leituras da porta e FFTs por segundo, independentes da taxa de quadros
+const UPS: u32 = 30;
 const FREQ_QUANTITY: i32 = 40*FREQ_DIVISOR;
+// Pontos da curva interpolada na faixa visível, qualquer que seja o zoom
+const CURVE_POINTS: usize = 512;
 static mut Y_MAX: i32 = 0;
 static mut NORM: f32 = 1.0;
 
 fn main() {
+    let source = frequency_reader::serial_source();
     let (tx, rx) = mpsc::sync_channel(LENGTH);
 
+    let diagnostics = Diagnostics::new();
+    let counters = diagnostics.counters();
     thread::spawn(move || {
-        frequency_reader::read_port(tx);
+        frequency_reader::read_port(source, tx, counters);
     });
 
-    display(&rx);
+    display(&rx, diagnostics);
 }
 
-fn display(rx: &Receiver<isize>) {
-
-    let mut samples = BoundedVecDeque::with_capacity(LENGTH, LENGTH);
+fn display(rx: &Receiver<SourceEvent<isize>>, diagnostics: Diagnostics) {
 
     let mut window: PistonWindow = WindowSettings::new("Frequências em Tempo Real", [1280, 720])
         .samples(4)
         .build()
         .unwrap();
 
-    // window.set_max_fps(FPS as u64);
+    let mut app = SpectrumApp {
+        rx,
+        samples: BoundedVecDeque::with_capacity(LENGTH, LENGTH),
+        spectrum: None,
+        // A roda do mouse aproxima e afasta as frequências, arrastar desloca; Home restaura
+        view: ChartView::new(0.0..FREQ_QUANTITY as f64, 0.0..1.0).with_axes(true, false),
+        diagnostics,
+        keys: KeyBindings::new()
+            .bind(piston_window::Key::D, Command::DisplayMode, "curva crua, interpolada ou barras")
+            .bind(piston_window::Key::W, Command::WindowFunction, "próxima função de janela")
+            .bind(piston_window::Key::S, Command::Scaling, "próxima escala")
+            .bind(piston_window::Key::Space, Command::Freeze, "congela e retoma o quadro")
+            .bind(piston_window::Key::Y, Command::ResetYMax, "recomeça Y_MAX")
+            .bind(piston_window::Key::Home, Command::ResetZoom, "restaura o zoom")
+            .bind(piston_window::Key::F12, Command::Screenshot, "salva o quadro em PNG")
+            .bind(piston_window::Key::R, Command::Record, "inicia e encerra a gravação")
+            .bind(piston_window::Key::F3, Command::Diagnostics, "mostra e esconde o diagnóstico")
+            .help(piston_window::Key::F1, "mostra e esconde esta ajuda"),
+        frozen: false,
+        display: DisplayMode::Interpolated,
+        window: WindowFunction::Hann,
+        scaling: Scaling::Raw,
+        screenshot: false,
+        recorder: None,
+    };
+
+    // Malha, rótulos e legendas são redesenhados só quando Y_MAX, o zoom, os modos ou o tamanho da janela mudam
+    run_piston_app(&mut window, &mut app);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Command {
+    DisplayMode,
+    WindowFunction,
+    Scaling,
+    Freeze,
+    ResetYMax,
+    ResetZoom,
+    Screenshot,
+    Record,
+    Diagnostics,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DisplayMode {
+    Raw,
+    Interpolated,
+    Histogram,
+}
+
+impl DisplayMode {
+    fn next(self) -> Self {
+        match self {
+            DisplayMode::Raw => DisplayMode::Interpolated,
+            DisplayMode::Interpolated => DisplayMode::Histogram,
+            DisplayMode::Histogram => DisplayMode::Raw,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            DisplayMode::Raw => "curva crua",
+            DisplayMode::Interpolated => "curva interpolada",
+            DisplayMode::Histogram => "gráfico de barras",
+        }
+    }
+}
+
+// Função de janelamento aplicada às amostras antes da FFT
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum WindowFunction {
+    Hann,
+    Hamming,
+    BlackmanHarris4,
+    BlackmanHarris7,
+}
 
+impl WindowFunction {
+    fn next(self) -> Self {
+        match self {
+            WindowFunction::Hann => WindowFunction::Hamming,
+            WindowFunction::Hamming => WindowFunction::BlackmanHarris4,
+            WindowFunction::BlackmanHarris4 => WindowFunction::BlackmanHarris7,
+            WindowFunction::BlackmanHarris7 => WindowFunction::Hann,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            WindowFunction::Hann => "Hann",
+            WindowFunction::Hamming => "Hamming",
+            WindowFunction::BlackmanHarris4 => "Blackman-Harris (4 termos)",
+            WindowFunction::BlackmanHarris7 => "Blackman-Harris (7 termos)",
+        }
+    }
+
+    fn apply(self, samples: &[f32]) -> Vec<f32> {
+        match self {
+            WindowFunction::Hann => hann_window(samples),
+            WindowFunction::Hamming => hamming_window(samples),
+            WindowFunction::BlackmanHarris4 => blackman_harris_4term(samples),
+            WindowFunction::BlackmanHarris7 => blackman_harris_7term(samples),
+        }
+    }
+}
+
+// Normalização das magnitudes do espectro
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Scaling {
+    Raw,
+    Magnitude,
+    Norm,
+    Softmax,
+}
+
+impl Scaling {
+    fn next(self) -> Self {
+        match self {
+            Scaling::Raw => Scaling::Magnitude,
+            Scaling::Magnitude => Scaling::Norm,
+            Scaling::Norm => Scaling::Softmax,
+            Scaling::Softmax => Scaling::Raw,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Scaling::Raw => "sem escala",
+            Scaling::Magnitude => "ordem de grandeza",
+            Scaling::Norm => "norma",
+            Scaling::Softmax => "softmax",
+        }
+    }
+
+    unsafe fn calculate(self, window: WindowFunction, window_samples: &Vec<f32>) -> FrequencySpectrum {
+        match self {
+            Scaling::Raw => calculate_window(window, window_samples),
+            Scaling::Magnitude => calculate_window_magnitude(window, window_samples),
+            Scaling::Norm => calculate_window_norm(window, window_samples),
+            Scaling::Softmax => calculate_window_softmax(window, window_samples, 8.0),
+        }
+    }
+}
+
+struct SpectrumApp<'a> {
+    rx: &'a Receiver<SourceEvent<isize>>,
+    samples: BoundedVecDeque<isize>,
+    // Último espectro calculado, mantido na tela enquanto o quadro está congelado
+    spectrum: Option<Vec<(f64, f64)>>,
+    view: ChartView,
+    // FPS, taxa de amostragem, fila do canal, erros, descartes e tempo da FFT
+    diagnostics: Diagnostics,
+    keys: KeyBindings<Command>,
+    // O quadro congelado continua na tela; as amostras continuam sendo lidas
+    frozen: bool,
+    display: DisplayMode,
+    window: WindowFunction,
+    scaling: Scaling,
+    // Salva o próximo quadro desenhado como PNG no diretório atual
+    screenshot: bool,
+    // Gravação da sessão em Y4M, a 30 quadros por segundo
+    recorder: Option<FrameRecorder>,
+}
+
+impl<'a> SpectrumApp<'a> {
+    fn run(&mut self, command: Command) {
+        match command {
+            Command::DisplayMode => self.display = self.display.next(),
+            Command::WindowFunction => {
+                self.window = self.window.next();
+                reset_y_max();
+            }
+            Command::Scaling => {
+                // Cada escala tem sua própria faixa; Y_MAX recomeça e a malha é redesenhada
+                self.scaling = self.scaling.next();
+                reset_y_max();
+            }
+            Command::Freeze => self.frozen = !self.frozen,
+            Command::ResetYMax => reset_y_max(),
+            Command::ResetZoom => self.view.reset(),
+            Command::Diagnostics => self.diagnostics.toggle(),
+            Command::Screenshot => self.screenshot = true,
+            Command::Record => match self.recorder.take() {
+                Some(recorder) => {
+                    let (frames, path) = (recorder.frames(), recorder.path().map(|p| p.to_path_buf()));
+                    match recorder.finish() {
+                        Ok(()) => println!("Gravação encerrada: {} quadros em {:?}", frames, path),
+                        Err(e) => eprintln!("Falha ao encerrar a gravação: {}", e),
+                    }
+                }
+                None => self.recorder = Some(FrameRecorder::new(".", FrameFormat::Y4m, 30.0)),
+            },
+        }
+    }
+}
+
+fn reset_y_max() {
+    unsafe {
+        Y_MAX = 0;
+        NORM = 1.0;
+    }
+}
+
+impl<'a> PistonApp for SpectrumApp<'a> {
+    type Key = (i32, Range<f64>, DisplayMode, WindowFunction, Scaling);
+    type Chart = ChartState<Cartesian2d<RangedCoordf32, RangedCoordf32>>;
+
+    fn layer_key(&self) -> Self::Key {
+        (unsafe { Y_MAX }, self.view.x_range(), self.display, self.window, self.scaling)
+    }
+
+    fn rates(&self) -> LoopRates {
+        LoopRates { ups: UPS as u64, max_fps: FPS as u64 }
+    }
 
-    while let Some(_) = draw_piston_window(&mut window, |b| unsafe {
-        for value in rx.try_iter().take(LENGTH/FREQ_DIVISOR as usize) {
-            samples.push_back(value);
+    fn update(&mut self, _window: &mut PistonWindow, _args: &UpdateArgs) {
+        let mut received = 0;
+        for event in self.rx.try_iter() {
+            match event {
+                SourceEvent::Sample(value) => {
+                    self.samples.push_back(value);
+                }
+                // As amostras de antes da desconexão não são emendadas às de depois
+                SourceEvent::Gap(_) => self.samples.clear(),
+            }
+            received += 1;
         }
+        self.diagnostics.set_backlog(received, LENGTH);
 
-        if samples.len() != LENGTH {
+        if received == 0 || self.frozen || self.samples.len() != LENGTH {
             //println!("\nIndata: ({}) {:?}", indata.len(), indata);
-            return Ok(())
+            return;
         }
 
-        let window_samples: Vec<f32> = samples.iter().map(|&x| x as f32).collect();
+        let window_samples: Vec<f32> = self.samples.iter().map(|&x| x as f32).collect();
         // println!("{:?}", samples);
 
-        let spectrum_window = calculate_window(&window_samples);
-        // let spectrum_window = calculate_window_softmax(&window_samples, 8.0);
-        // let spectrum_window = calculate_window_norm(&window_samples);
-        // let spectrum_window = calculate_window_bh(&window_samples);
+        let (scaling, window) = (self.scaling, self.window);
+        let spectrum_window = self.diagnostics.measure(|| unsafe { scaling.calculate(window, &window_samples) });
 
         // for (fr, fr_val) in spectrum_window.data().iter() {
         //     println!("{}Hz => {}", fr, fr_val)
         // }
 
+        self.spectrum = Some(spectrum_window.data().iter()
+            .map(|(x, y)| (x.val() as f64, y.val() as f64))
+            .collect());
+        let y_max = unsafe { Y_MAX };
+        self.view.set_limits(0.0..FREQ_QUANTITY as f64, 0.0..y_max as f64);
+    }
+
+    fn fell_behind(&mut self, lag: Duration) {
+        eprintln!("Processamento atrasado em {:?}; atualizações descartadas", lag);
+    }
+
+    fn draw_static(&mut self, b: PistonBackend) -> Result<Self::Chart, Box<dyn Error>> {
+        let scale = b.scale();
         let root = b.into_drawing_area();
         root.fill(&WHITE)?;
 
-        /* curva crua (x:f32)
-        let range_x = (0f32..FREQ_QUANTITY as f32);
-        let range_y = (0f32..Y_MAX as f32);
-        let x_axis_formatter = Some(&(|&x: &f32| format!("{}",(x/FREQ_DIVISOR as f32) )));
-        // */
-
-        // /* curva interpolada (x:i32)
-        let range_x = (0..FREQ_QUANTITY);
-        let range_y = (0f32..Y_MAX as f32);
-        let x_axis_formatter = Some(&(|&x: &i32| format!("{}",(x/FREQ_DIVISOR) )) );
-        // */
-
-        /* gráfico de barras
-        let range_x = (0..FREQ_QUANTITY/FREQ_DIVISOR).into_segmented();
-        let range_y = (0..Y_MAX);
-        let x_axis_formatter =
-            None;
-        // */
+        let y_max = unsafe { Y_MAX };
+
+        // Frequências na faixa visível; o rótulo mostra Hz
+        let visible = self.view.x_range();
+        let range_x = (visible.start as f32..visible.end as f32);
+        let range_y = (0f32..y_max as f32);
+        let x_axis_formatter = |&x: &f32| format!("{:.1}",(x/FREQ_DIVISOR as f32) );
+
+        let caption = format!("{}, janela {}, {} (F1: ajuda)",
+                              self.display.name(), self.window.name(), self.scaling.name());
 
         let mut ctx =
             ChartBuilder::on(&root)
                 .margin(40)
-                .caption("", ("sans-serif", 40))
+                .caption(caption, ("sans-serif", 20))
                 .set_label_area_size(LabelAreaPosition::Left, 60)
                 .set_label_area_size(LabelAreaPosition::Bottom, 40)
                 // .set_label_area_size(LabelAreaPosition::Right, 60)
                 .build_cartesian_2d(
                     range_x, range_y
-                )
-                .unwrap();
-
-        let mut binding = ctx.configure_mesh();
-        let mut mesh_builder =
-            binding
-                .x_desc("Frequências")
-                .y_desc(format!("Magnitude (máxima: {})", Y_MAX))
-                .axis_desc_style(("sans-serif", 20))
-                .y_label_formatter(&(|&y| format!("{:.1}%",100.0*(y as f32/Y_MAX as f32))));
-
-        let mesh_builder = match x_axis_formatter{
-            Some(fmt) => mesh_builder.x_label_formatter(fmt),
-            None => mesh_builder
+                )?;
+
+        ctx.configure_mesh()
+            .x_desc("Frequências")
+            .y_desc(format!("Magnitude (máxima: {})", y_max))
+            .axis_desc_style(("sans-serif", 20))
+            .x_label_formatter(&x_axis_formatter)
+            .y_label_formatter(&(|&y| format!("{:.1}%",100.0*(y as f32/y_max as f32))))
+            .draw()?;
+
+        self.view.set_plotting_area(ctx.plotting_area().get_pixel_range(), scale);
+
+        Ok(ctx.into_chart_state())
+    }
+
+    fn draw(&mut self, b: PistonBackend, chart: &Self::Chart) -> DrawResult {
+        let spectrum = match &self.spectrum {
+            Some(spectrum) => spectrum,
+            None => return Ok(()),
         };
 
-        mesh_builder.draw().unwrap();
+        // Ponto do espectro mais próximo do cursor, com a cruz e a leitura dos valores
+        let hovered = self.view.snap(spectrum.iter().cloned());
+
+        let gradient = b.fill_gradient();
+        let pattern = b.line_pattern();
+        let clip = b.clip();
+        let root = b.into_drawing_area();
+        let ctx = chart.clone().restore(&root);
+
+        // A curva não invade eixos e rótulos quando passa de Y_MAX
+        let plotting_area = ctx.plotting_area().get_pixel_range();
+        clip.clipped(plotting_area.clone(), || match self.display {
+            DisplayMode::Raw => draw_curve(ctx, spectrum),
+            DisplayMode::Interpolated => {
+                let curve = interpolate_spectrum(spectrum, self.view.x_range());
+                draw_interpolated_curve(ctx, &curve, &gradient)
+            }
+            DisplayMode::Histogram => draw_histogram(ctx, spectrum),
+        })?;
+
+        if let Some(point) = hovered {
+            let ctx = chart.clone().restore(&root);
+            pattern.set(LinePattern::Dashed { dash: 6.0, gap: 4.0 });
+            let result = clip.clipped(plotting_area, || draw_crosshair(&ctx, &self.view, point));
+            pattern.set(LinePattern::Solid);
+            result?;
+            draw_readout(&root, &ctx, point)?;
+        }
+
+        Ok(())
+    }
 
-        // draw_curve(ctx, spectrum_window);
-        draw_interpolated_curve(ctx, spectrum_window);
-        // draw_histogram(ctx, spectrum_window);
+    fn draw_overlay(&mut self, mut b: PistonBackend) -> DrawResult {
+        self.diagnostics.frame(Instant::now());
 
+        let font = TextStyle::from(("sans-serif", 18).into_font());
+        self.keys.draw_help(&mut b, &font)?;
+        self.diagnostics.draw(&mut b, &font)?;
+        self.diagnostics.draw_connection(&mut b, &font)?;
         Ok(())
-    }){}
+    }
+
+    fn event(&mut self, _window: &mut PistonWindow, event: &Event, _chart: Option<&Self::Chart>) {
+        self.view.handle_event(event);
+
+        if let Some(command) = self.keys.command(event) {
+            self.run(command);
+        }
+    }
+
+    fn after_frame(&mut self, window: &mut PistonWindow, event: &Event) {
+        if let Some(rec) = self.recorder.as_mut() {
+            if let Err(e) = rec.capture(window, event) {
+                eprintln!("Falha ao gravar o quadro: {}", e);
+                self.recorder = None;
+            }
+        }
+        if self.screenshot {
+            self.screenshot = false;
+            match save_screenshot(window, ".") {
+                Ok(path) => println!("Captura salva em {}", path.display()),
+                Err(e) => eprintln!("Falha ao salvar a captura: {}", e),
+            }
+        }
+    }
+
+    fn draw_failed(&mut self, e: Box<dyn Error>) {
+        eprintln!("Falha ao desenhar o gráfico: {}", e);
+    }
 }
 
-fn draw_curve(mut ctx: ChartContext<PistonBackend, Cartesian2d<RangedCoordf32, RangedCoordf32>>, spectrum_window: FrequencySpectrum) {
+fn draw_curve(mut ctx: ChartContext<PistonBackend, Cartesian2d<RangedCoordf32, RangedCoordf32>>, spectrum: &[(f64, f64)]) -> DrawResult {
     let curva =
-        spectrum_window.data().iter()
-            .map(|(x, y)|
-                (x.val() as f32, y.val() as f32))
+        spectrum.iter()
+            .map(|&(x, y)|
+                (x as f32, y as f32))
         ;
 
-    ctx.draw_series(LineSeries::new(curva, &RED)).unwrap();
+    ctx.draw_series(LineSeries::new(curva, &RED))?;
+    Ok(())
 }
 
-fn draw_histogram(mut ctx: ChartContext<PistonBackend, Cartesian2d<SegmentedCoord<RangedCoordi32>, RangedCoordi32>>, spectrum_window: FrequencySpectrum) {
-    let data = interpolate_values_set((0..FREQ_QUANTITY),
-                                      spectrum_window.data().iter()
-                                          .map(|(x, y)|
-                                              (x.val() as f64, y.val() as f64))
-                                          .collect::<Vec<_>>().as_slice());
+fn draw_histogram(mut ctx: ChartContext<PistonBackend, Cartesian2d<RangedCoordf32, RangedCoordf32>>, spectrum: &[(f64, f64)]) -> DrawResult {
+    let data = interpolate_values_set((0..FREQ_QUANTITY), spectrum);
 
     ctx.draw_series(
         data.iter()
             .map(|&(x, y)| {
-                let x_0 = SegmentValue::Exact(x/FREQ_DIVISOR as i32);
-                let x_1 = SegmentValue::Exact((x/FREQ_DIVISOR + 1) as i32);
-                let y = y as i32;
-                let mut bar = Rectangle::new([(x_0, 0), (x_1, y)], RED.filled());
+                // Uma barra por Hz
+                let x_0 = (x/FREQ_DIVISOR * FREQ_DIVISOR) as f32;
+                let x_1 = x_0 + FREQ_DIVISOR as f32;
+                let mut bar = Rectangle::new([(x_0, 0.0), (x_1, y as f32)], RED.filled());
                 bar.set_margin(0, 0, 1, 1);
                 bar
             })
-    )
-        .unwrap();
+    )?;
+    Ok(())
 }
 
-fn draw_interpolated_curve(mut ctx: ChartContext<PistonBackend, Cartesian2d<RangedCoordi32, RangedCoordf32>>, spectrum_window: FrequencySpectrum) {
-    let data = interpolate_values_set((0..FREQ_QUANTITY),
-                                      spectrum_window.data().iter()
-                                          .map(|(x, y)|
-                                              (x.val() as f64, y.val() as f64))
-                                          .collect::<Vec<_>>().as_slice());
+// Bandas do EEG em Hz, cada uma com sua cor no preenchimento sob a curva
+const BANDS: [(i32, i32, RGBColor); 5] = [
+    (0, 4, RGBColor(120, 80, 200)),   // delta
+    (4, 8, RGBColor(60, 120, 220)),   // teta
+    (8, 13, RGBColor(40, 170, 90)),   // alfa
+    (13, 30, RGBColor(230, 170, 30)), // beta
+    (30, 40, RGBColor(220, 70, 60)),  // gama
+];
+
+fn band_gradient(ctx: &ChartContext<PistonBackend, Cartesian2d<RangedCoordf32, RangedCoordf32>>) -> FillGradient {
+    let mut stops = Vec::new();
+    for &(from, to, color) in BANDS.iter() {
+        let color = color.mix(0.35).to_backend_color();
+        stops.push((ctx.backend_coord(&((from * FREQ_DIVISOR) as f32, 0.0)).0 as f32, color));
+        stops.push((ctx.backend_coord(&((to * FREQ_DIVISOR) as f32, 0.0)).0 as f32, color));
+    }
+    FillGradient::horizontal(stops)
+}
 
-    let curva =
-        data.iter()
-            .cloned()
-            .map(|(x, y)| (x, y as f32))
-        ;
+fn draw_crosshair(ctx: &ChartContext<PistonBackend, Cartesian2d<RangedCoordf32, RangedCoordf32>>, view: &ChartView, (x, y): (f64, f64)) -> DrawResult {
+    let (x, y) = (x as f32, y as f32);
+    let (range_x, y_max) = (view.x_range(), unsafe { Y_MAX } as f32);
+    let style = BLACK.mix(0.6);
+
+    ctx.plotting_area().draw(&PathElement::new(vec![(x, 0.0), (x, y_max)], &style))?;
+    ctx.plotting_area().draw(&PathElement::new(vec![(range_x.start as f32, y), (range_x.end as f32, y)], &style))?;
+    Ok(())
+}
+
+// Frequência em Hz e magnitude do ponto, numa caixa ao lado da cruz
+fn draw_readout(root: &DrawingArea<PistonBackend, Shift>, ctx: &ChartContext<PistonBackend, Cartesian2d<RangedCoordf32, RangedCoordf32>>, (x, y): (f64, f64)) -> DrawResult {
+    let (px, py) = ctx.backend_coord(&(x as f32, y as f32));
+    let y_max = unsafe { Y_MAX }.max(1) as f64;
+    let lines = [
+        format!("{:.2} Hz", x / FREQ_DIVISOR as f64),
+        format!("{:.1} ({:.1}%)", y, 100.0 * y / y_max),
+    ];
+
+    let (w, h) = (150, 48);
+    let (width, _) = root.dim_in_pixel();
+    let left = if px + 12 + w > width as i32 { px - 12 - w } else { px + 12 };
+    let top = (py - h - 8).max(0);
+
+    root.draw(&Rectangle::new([(left, top), (left + w, top + h)], WHITE.mix(0.9).filled()))?;
+    root.draw(&Rectangle::new([(left, top), (left + w, top + h)], &BLACK))?;
+    for (i, line) in lines.iter().enumerate() {
+        root.draw(&Text::new(line.clone(), (left + 8, top + 6 + 20 * i as i32), ("sans-serif", 16)))?;
+    }
+    Ok(())
+}
 
-    ctx.draw_series(LineSeries::new(curva, &RED)).unwrap();
+fn interpolate_spectrum(spectrum: &[(f64, f64)], range: Range<f64>) -> Vec<(f32, f32)> {
+    interpolate_values_between(range, CURVE_POINTS, spectrum)
+        .iter()
+        .map(|&(x, y)| (x as f32, y as f32))
+        .collect()
 }
 
-unsafe fn calculate_window_bh(window_samples: &Vec<f32>) -> FrequencySpectrum{
-    let fft_window = blackman_harris_7term(&window_samples);
+fn draw_interpolated_curve(mut ctx: ChartContext<PistonBackend, Cartesian2d<RangedCoordf32, RangedCoordf32>>, curve: &[(f32, f32)], gradient: &FillGradientHandle) -> DrawResult {
+    let curva = curve.iter().cloned();
+
+    gradient.set(Some(band_gradient(&ctx)));
+    let area = ctx.draw_series(AreaSeries::new(curva.clone(), 0.0, &RED.mix(0.35)));
+    // O gradiente não pode sobrar para a linha, nem para o próximo quadro, se a área falhar
+    gradient.set(None);
+    area?;
+
+    ctx.draw_series(LineSeries::new(curva, &RED))?;
+    Ok(())
+}
+
+unsafe fn calculate_window_magnitude(window: WindowFunction, window_samples: &Vec<f32>) -> FrequencySpectrum{
+    let fft_window = window.apply(&window_samples);
 
     let max = fft_window.iter().fold(0.0, |pivot, &x| if pivot > x {pivot} else {x});
     let magnitude = (magnitude_adjust_factor(max as f64)+1).max((Y_MAX as f32).log10() as i32);
@@ -215,8 +565,8 @@ unsafe fn calculate_window_bh(window_samples: &Vec<f32>) -> FrequencySpectrum{
     ).unwrap()
 }
 
-unsafe fn calculate_window_norm(window_samples: &Vec<f32>) -> FrequencySpectrum{
-    let fft_window = hann_window(&window_samples);
+unsafe fn calculate_window_norm(window: WindowFunction, window_samples: &Vec<f32>) -> FrequencySpectrum{
+    let fft_window = window.apply(&window_samples);
 
     NORM = window_samples.iter().fold(0.0, |sum, &num| sum + num.powf(2.0)).sqrt();
     if NORM < 1.0{
@@ -237,7 +587,7 @@ unsafe fn calculate_window_norm(window_samples: &Vec<f32>) -> FrequencySpectrum{
     ).unwrap()
 }
 
-unsafe fn calculate_window_softmax(window_samples: &Vec<f32>, temperature: f32) -> FrequencySpectrum{
+unsafe fn calculate_window_softmax(window: WindowFunction, window_samples: &Vec<f32>, temperature: f32) -> FrequencySpectrum{
     let max = window_samples.iter().fold(0.0, |pivot, &x| if pivot > x {pivot} else {x});
 
     let exp_window: Vec<f32> =  window_samples.iter().map(|&x| ((x/max)/temperature).exp() ).collect();
@@ -246,7 +596,7 @@ unsafe fn calculate_window_softmax(window_samples: &Vec<f32>, temperature: f32)
 
     let sf_normalized_window: Vec<f32> = exp_window.iter().map(|&x| x/NORM).collect();
 
-    let fft_window = hann_window(&sf_normalized_window);
+    let fft_window = window.apply(&sf_normalized_window);
 
     let mean = fft_window.iter().fold(0.0, |pivot, &x| pivot+x) as f64/LENGTH as f64;
 
@@ -266,8 +616,8 @@ unsafe fn calculate_window_softmax(window_samples: &Vec<f32>, temperature: f32)
     ).unwrap()
 }
 
-unsafe fn calculate_window(window_samples: &Vec<f32>) -> FrequencySpectrum{
-    let fft_window = hann_window(&window_samples);
+unsafe fn calculate_window(window: WindowFunction, window_samples: &Vec<f32>) -> FrequencySpectrum{
+    let fft_window = window.apply(&window_samples);
 
     samples_fft_to_spectrum(
         &fft_window,
@@ -302,6 +652,21 @@ fn interpolate_values_set(range: Range<i32>, points: &[(f64, f64)]) -> Vec<(i32,
     interpolated_values
 }
 
+fn interpolate_values_between(range: Range<f64>, count: usize, points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let keys: Vec<Key<f64, f64>> =
+        points.iter().map(|&(x, y)|
+            Key::new(x, y, Interpolation::CatmullRom)).collect();
+
+    let spline = Spline::from_vec(keys);
+
+    // `count` pontos igualmente espaçados, incluindo as duas extremidades
+    let step = (range.end - range.start) / (count.max(2) - 1) as f64;
+    (0..count.max(2))
+        .map(|i| range.start + step * i as f64)
+        .map(|x_new| (x_new, spline.clamped_sample(x_new).unwrap_or(0.0)))
+        .collect()
+}
+
 fn magnitude_adjust_factor(num: f64) -> i32 {
     if num>1.0{
         return 1+num.log10() as i32;
@@ -0,0 +1,173 @@
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+use piston_window::{Event, EventLoop, PistonWindow, RenderEvent, UpdateArgs, UpdateEvent};
+
+use crate::backend::{draw_layered_frame, DrawResult, PistonBackend};
+use crate::cache::RenderCache;
+use crate::layer::StaticLayer;
+
+/// Rates of the loop run by `run_piston_app`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LoopRates {
+    /// Fixed rate of `PistonApp::update`, in updates per second.
+    pub ups: u64,
+    /// Highest rate at which frames are drawn, in frames per second.
+    pub max_fps: u64,
+}
+
+impl Default for LoopRates {
+    /// The defaults of piston: 120 updates and at most 60 frames per second.
+    fn default() -> Self {
+        Self {
+            ups: 120,
+            max_fps: 60,
+        }
+    }
+}
+
+/// Interactive viewer driven by `run_piston_app`.
+///
+/// The frame is drawn like in `try_draw_piston_window_layered`: `draw_static`
+/// draws the static part of the charts whenever `layer_key` or the window size
+/// changes, and `draw` draws the live part over it every frame. Reading the data
+/// sources and processing them belongs in `update`, which runs at the fixed rate
+/// of `rates` whatever the frame rate, so that the throughput does not depend on
+/// how fast the window draws.
+///
+/// Input events (keyboard, mouse, resize, ...) are handed to `event`, together
+/// with the state returned by the last successful `draw_static`, so the viewer
+/// can change its parameters at runtime. Changing something that `layer_key`
+/// returns redraws the static part on the next frame.
+pub trait PistonApp {
+    /// Identifies the content of the static layer, usually the axis ranges and
+    /// display mode.
+    type Key: PartialEq;
+    /// What the static drawing hands to the live drawing, usually the plotters
+    /// `ChartState` of the chart.
+    type Chart;
+
+    fn layer_key(&self) -> Self::Key;
+
+    /// Update and frame rates, read once when the loop starts.
+    fn rates(&self) -> LoopRates {
+        LoopRates::default()
+    }
+
+    /// Advance the application by one fixed step of `args.dt` seconds.
+    fn update(&mut self, _window: &mut PistonWindow, _args: &UpdateArgs) {}
+
+    /// Called when updates fall so far behind their fixed rate that piston skips
+    /// the missed ones, with how late the update that noticed it was. Called
+    /// once until the updates keep up again.
+    fn fell_behind(&mut self, _lag: Duration) {}
+
+    fn draw_static(&mut self, backend: PistonBackend) -> Result<Self::Chart, Box<dyn Error>>;
+
+    fn draw(&mut self, backend: PistonBackend, chart: &Self::Chart) -> DrawResult;
+
+    /// Draw over the charts, for example a help panel. Called every frame after
+    /// `draw`, even while the static part cannot be drawn.
+    fn draw_overlay(&mut self, _backend: PistonBackend) -> DrawResult {
+        Ok(())
+    }
+
+    /// Handle an event that neither renders a frame nor updates the application.
+    /// `chart` is `None` until the static part has been drawn.
+    fn event(&mut self, _window: &mut PistonWindow, _event: &Event, _chart: Option<&Self::Chart>) {}
+
+    /// Called after each frame, while the frame can still be read back with
+    /// `read_frame`, for screenshots or recordings.
+    fn after_frame(&mut self, _window: &mut PistonWindow, _event: &Event) {}
+
+    /// Called with the error of a frame that could not be drawn. Errors are
+    /// discarded by default, as in `draw_piston_window`.
+    fn draw_failed(&mut self, _error: Box<dyn Error>) {}
+}
+
+/// Tells when update events arrive later than the event loop tolerates.
+struct UpdateClock {
+    period: Duration,
+    tolerance: Duration,
+    due: Option<Instant>,
+    behind: bool,
+}
+
+impl UpdateClock {
+    fn new(window: &PistonWindow) -> Self {
+        let settings = window.get_event_settings();
+        let period = Duration::from_secs_f64(1.0 / settings.ups as f64);
+        Self {
+            period,
+            // Piston skips the updates that are more than `ups_reset` periods late
+            tolerance: period * settings.ups_reset.max(1) as u32,
+            due: None,
+            behind: false,
+        }
+    }
+
+    /// Account for an update at `now`, and return how late it is when the loop
+    /// has just fallen behind.
+    fn tick(&mut self, now: Instant) -> Option<Duration> {
+        let due = *self.due.get_or_insert(now);
+        let lag = now.saturating_duration_since(due);
+        let late = lag > self.tolerance;
+        // Like piston, start over from now instead of catching up the skipped updates
+        let from = if late { now } else { due };
+        self.due = Some(from + self.period);
+        let fell_behind = late && !self.behind;
+        if lag <= self.period {
+            self.behind = false;
+        } else if late {
+            self.behind = true;
+        }
+        if fell_behind {
+            Some(lag)
+        } else {
+            None
+        }
+    }
+}
+
+/// Run `app` in `window` until the window is closed.
+pub fn run_piston_app<A: PistonApp>(window: &mut PistonWindow, app: &mut A) {
+    let rates = app.rates();
+    window.set_ups(rates.ups);
+    window.set_max_fps(rates.max_fps);
+
+    let mut cache = RenderCache::new(window);
+    let mut layer = StaticLayer::new();
+    let mut clock = UpdateClock::new(window);
+    while let Some(event) = window.next() {
+        if let Some(args) = event.update_args() {
+            if let Some(lag) = clock.tick(Instant::now()) {
+                app.fell_behind(lag);
+            }
+            app.update(window, &args);
+            continue;
+        }
+        let args = match event.render_args() {
+            Some(args) => args,
+            None => {
+                app.event(window, &event, layer.state());
+                continue;
+            }
+        };
+        let key = app.layer_key();
+        let result = draw_layered_frame(
+            window,
+            &mut cache,
+            &mut layer,
+            &args,
+            app,
+            key,
+            |app, backend| app.draw_static(backend),
+            |app, backend, chart| app.draw(backend, chart),
+            |app, backend| app.draw_overlay(backend),
+        );
+        if let Err(error) = result {
+            app.draw_failed(error);
+        }
+        app.after_frame(window, &event);
+    }
+}
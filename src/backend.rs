@@ -1,31 +1,46 @@
+use std::marker::PhantomData;
+
+use lyon_tessellation::math::{point, Point};
 use piston_window::context::Context;
-use piston_window::ellipse::circle;
-use piston_window::{circle_arc, ellipse, line, rectangle, Event, Loop};
-use piston_window::{G2d, PistonWindow};
+use piston_window::{triangulation, DrawState, Event, Graphics, Image, Loop, Transformed};
+use piston_window::{G2d, G2dTextureContext, PistonWindow, RenderArgs, RenderEvent};
 
+use plotters_backend::text_anchor::{HPos, VPos};
 use plotters_backend::{
-    BackendColor, BackendCoord, BackendStyle, DrawingBackend, DrawingErrorKind,
+    BackendColor, BackendCoord, BackendStyle, BackendTextStyle, DrawingBackend, DrawingErrorKind,
+    FontTransform,
 };
+use texture::{CreateTexture, UpdateTexture};
 
-#[derive(Debug)]
-pub struct DummyBackendError;
-
-impl std::fmt::Display for DummyBackendError {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(fmt, "{:?}", self)
-    }
-}
-
-impl std::error::Error for DummyBackendError {}
+use crate::batch::TriangleBatch;
+use crate::cache::RenderCache;
+use crate::clip::{intersect_scissors, ClipHandle};
+use crate::error::PistonBackendError;
+use crate::layer::{StaticLayer, LAYER_BACKGROUND};
+use crate::pattern::{FillGradientHandle, LinePattern, LinePatternHandle};
+use crate::tessellate;
 
-pub struct PistonBackend<'a, 'b> {
+/// plotters backend drawing through any piston `Graphics` implementation.
+///
+/// `G` defaults to the `G2d` of `PistonWindow`, and `F` to the texture factory of
+/// its `RenderCache`. Other backends, such as `opengl_graphics` or a software
+/// renderer, only need their own `RenderCache` when text and bitmaps should be
+/// drawn from textures.
+pub struct PistonBackend<'a, 'b, G: Graphics = G2d<'a>, F = G2dTextureContext> {
     size: (u32, u32),
     scale: f64,
     context: Context,
-    graphics: &'b mut G2d<'a>,
+    graphics: &'b mut G,
+    cache: Option<&'b mut RenderCache<G::Texture, F>>,
+    batch: TriangleBatch,
+    batch_state: DrawState,
+    clip: ClipHandle,
+    line_pattern: LinePatternHandle,
+    fill_gradient: FillGradientHandle,
+    marker: PhantomData<&'a ()>,
 }
 
-fn make_piston_rgba(color: &BackendColor) -> [f32; 4] {
+pub(crate) fn make_piston_rgba(color: &BackendColor) -> [f32; 4] {
     let (r, g, b) = color.rgb;
     let a = color.alpha;
 
@@ -36,38 +51,213 @@ fn make_piston_rgba(color: &BackendColor) -> [f32; 4] {
         a as f32,
     ]
 }
-fn make_point_pair(a: BackendCoord, b: BackendCoord, scale: f64) -> [f64; 4] {
-    [
-        a.0 as f64 * scale,
-        a.1 as f64 * scale,
-        b.0 as f64 * scale,
-        b.1 as f64 * scale,
-    ]
+fn make_point_pair(a: BackendCoord, b: BackendCoord) -> [f64; 4] {
+    [a.0 as f64, a.1 as f64, b.0 as f64, b.1 as f64]
+}
+fn make_point(a: BackendCoord) -> Point {
+    point(a.0 as f32, a.1 as f32)
+}
+/// Piston line radius for a plotters stroke, in backend pixels.
+fn make_stroke_radius<S: BackendStyle>(style: &S) -> f64 {
+    style.stroke_width().max(1) as f64 / 2.0
 }
 
-impl<'a, 'b> PistonBackend<'a, 'b> {
-    pub fn new(size: (u32, u32), scale: f64, context: Context, graphics: &'b mut G2d<'a>) -> Self {
+impl<'a, 'b, G: Graphics, F> PistonBackend<'a, 'b, G, F> {
+    /// `size` is the drawing area in backend pixels and `scale` the ratio between
+    /// the window coordinates of `context` and those pixels. Every primitive is
+    /// drawn in backend pixels through a single transform that applies `scale`.
+    ///
+    /// The texture factory `F` is that of the `RenderCache` given to
+    /// `with_cache`, or else the one the backend is annotated with.
+    pub fn new(size: (u32, u32), scale: f64, context: Context, graphics: &'b mut G) -> Self {
         Self {
             size,
-            context,
+            context: context.scale(scale, scale),
             graphics,
             scale,
+            cache: None,
+            batch: TriangleBatch::default(),
+            batch_state: context.draw_state,
+            clip: ClipHandle::default(),
+            line_pattern: LinePatternHandle::default(),
+            fill_gradient: FillGradientHandle::default(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Ratio between window coordinates and backend pixels, 0.5 on a 2x HiDPI screen.
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Draw text and bitmaps through the textures of `cache` instead of pixel by pixel.
+    pub fn with_cache(mut self, cache: &'b mut RenderCache<G::Texture, F>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Handle to the clip rectangle of this backend, which does not clip until
+    /// it is set.
+    pub fn clip(&self) -> ClipHandle {
+        self.clip.clone()
+    }
+
+    /// Handle to the pattern of the lines and paths drawn by this backend, solid
+    /// until it is changed.
+    pub fn line_pattern(&self) -> LinePatternHandle {
+        self.line_pattern.clone()
+    }
+
+    /// Handle to the gradient polygons drawn by this backend are filled with,
+    /// none until it is set.
+    pub fn fill_gradient(&self) -> FillGradientHandle {
+        self.fill_gradient.clone()
+    }
+
+    /// Submit the primitives batched so far. Called before anything that is not
+    /// part of the batch is drawn, so the drawing order is kept.
+    fn flush(&mut self) {
+        self.batch.flush(&self.batch_state, self.graphics);
+    }
+
+    /// Draw state of the context, with the clip rectangle as scissor.
+    fn draw_state(&self) -> DrawState {
+        let mut draw_state = self.context.draw_state;
+        if let Some(clip) = self.clip.scissor() {
+            draw_state.scissor = Some(match draw_state.scissor {
+                Some(outer) => intersect_scissors(outer, clip),
+                None => clip,
+            });
         }
+        draw_state
+    }
+
+    /// Flush the batch if the clip rectangle changed since its primitives were
+    /// pushed, since one batch is submitted with a single draw state.
+    fn sync_batch(&mut self) {
+        let draw_state = self.draw_state();
+        if draw_state != self.batch_state {
+            self.flush();
+            self.batch_state = draw_state;
+        }
+    }
+
+    fn blit_bitmap_pixels(
+        &mut self,
+        pos: BackendCoord,
+        (iw, ih): (u32, u32),
+        src: &[u8],
+    ) -> Result<(), DrawingErrorKind<PistonBackendError>> {
+        if src.len() < iw as usize * ih as usize * 3 {
+            return Err(DrawingErrorKind::DrawingError(
+                PistonBackendError::BitmapSize {
+                    width: iw,
+                    height: ih,
+                    len: src.len(),
+                },
+            ));
+        }
+        let (w, h) = self.size;
+        for dy in 0..ih.min((h as i32 - pos.1).max(0) as u32) {
+            for dx in 0..iw.min((w as i32 - pos.0).max(0) as u32) {
+                let offset = (dx + dy * iw) as usize * 3;
+                let color = BackendColor {
+                    alpha: 1.0,
+                    rgb: (src[offset], src[offset + 1], src[offset + 2]),
+                };
+                self.push_pixel((pos.0 + dx as i32, pos.1 + dy as i32), color);
+            }
+        }
+        Ok(())
+    }
+
+    /// Batch a one pixel square, without the trait bounds of `draw_pixel`.
+    fn push_pixel(&mut self, point: BackendCoord, color: BackendColor) {
+        self.sync_batch();
+        self.batch.push_rect(
+            self.context.transform,
+            make_piston_rgba(&color),
+            make_point_pair(point, (1, 1)),
+        );
+    }
+
+    fn draw_text_pixels<TStyle: BackendTextStyle>(
+        &mut self,
+        text: &str,
+        style: &TStyle,
+        pos: BackendCoord,
+        offset: (i32, i32),
+        min: (i32, i32),
+    ) -> Result<(), DrawingErrorKind<PistonBackendError>> {
+        let trans = style.transform();
+        let (w, h) = self.size;
+        let drawing_result = style.draw(text, (0, 0), |x, y, color| {
+            let (x, y) = trans.transform(x + offset.0 - min.0, y + offset.1 - min.1);
+            let (x, y) = (pos.0 + x, pos.1 + y);
+            if x >= 0 && x < w as i32 && y >= 0 && y < h as i32 {
+                self.push_pixel((x, y), color);
+            }
+            Ok::<(), DrawingErrorKind<PistonBackendError>>(())
+        });
+        match drawing_result {
+            Ok(drawing_result) => drawing_result,
+            Err(font_error) => Err(DrawingErrorKind::FontError(Box::new(font_error))),
+        }
+    }
+
+    /// Stroke a polyline with the current line pattern.
+    fn stroke<S: BackendStyle>(
+        &mut self,
+        points: &[Point],
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<PistonBackendError>> {
+        self.sync_batch();
+        let color = make_piston_rgba(&style.color());
+        let radius = make_stroke_radius(style);
+        let transform = self.context.transform;
+        let dashes = match self.line_pattern.get() {
+            LinePattern::Solid => vec![points.to_vec()],
+            LinePattern::Dashed { dash, gap } => tessellate::dash_polyline(points, dash, gap),
+            LinePattern::Dotted { spacing } => {
+                for center in tessellate::dot_centers(points, spacing) {
+                    self.batch.push_disc(
+                        transform,
+                        color,
+                        [center.x as f64, center.y as f64],
+                        radius,
+                    );
+                }
+                return Ok(());
+            }
+        };
+        for dash in dashes {
+            let triangles =
+                tessellate::stroke_polyline(&dash, 2.0 * radius as f32).map_err(|e| {
+                    DrawingErrorKind::DrawingError(PistonBackendError::InvalidGeometry(e))
+                })?;
+            self.batch.push_triangles(transform, color, &triangles);
+        }
+        Ok(())
     }
 }
 
-impl<'a, 'b> DrawingBackend for PistonBackend<'a, 'b> {
-    type ErrorType = DummyBackendError;
+impl<'a, 'b, G, F> DrawingBackend for PistonBackend<'a, 'b, G, F>
+where
+    G: Graphics,
+    G::Texture: CreateTexture<F> + UpdateTexture<F>,
+{
+    type ErrorType = PistonBackendError;
 
     fn get_size(&self) -> (u32, u32) {
         self.size
     }
 
-    fn ensure_prepared(&mut self) -> Result<(), DrawingErrorKind<DummyBackendError>> {
+    fn ensure_prepared(&mut self) -> Result<(), DrawingErrorKind<PistonBackendError>> {
         Ok(())
     }
 
-    fn present(&mut self) -> Result<(), DrawingErrorKind<DummyBackendError>> {
+    fn present(&mut self) -> Result<(), DrawingErrorKind<PistonBackendError>> {
+        self.flush();
         Ok(())
     }
 
@@ -76,12 +266,7 @@ impl<'a, 'b> DrawingBackend for PistonBackend<'a, 'b> {
         point: BackendCoord,
         color: BackendColor,
     ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
-        piston_window::rectangle(
-            make_piston_rgba(&color),
-            make_point_pair(point, (1, 1), self.scale),
-            self.context.transform,
-            self.graphics,
-        );
+        self.push_pixel(point, color);
         Ok(())
     }
 
@@ -91,12 +276,15 @@ impl<'a, 'b> DrawingBackend for PistonBackend<'a, 'b> {
         to: BackendCoord,
         style: &S,
     ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
-        line(
-            make_piston_rgba(&style.color()),
-            self.scale,
-            make_point_pair(from, to, self.scale),
+        if self.line_pattern.get() != LinePattern::Solid {
+            return self.stroke(&[make_point(from), make_point(to)], style);
+        }
+        self.sync_batch();
+        self.batch.push_line(
             self.context.transform,
-            self.graphics,
+            make_piston_rgba(&style.color()),
+            make_stroke_radius(style),
+            make_point_pair(from, to),
         );
         Ok(())
     }
@@ -108,48 +296,70 @@ impl<'a, 'b> DrawingBackend for PistonBackend<'a, 'b> {
         style: &S,
         fill: bool,
     ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        self.sync_batch();
         if fill {
-            rectangle(
+            self.batch.push_rect(
+                self.context.transform,
                 make_piston_rgba(&style.color()),
                 make_point_pair(
                     upper_left,
                     (bottom_right.0 - upper_left.0, bottom_right.1 - upper_left.1),
-                    self.scale,
                 ),
-                self.context.transform,
-                self.graphics,
             );
+        } else if self.line_pattern.get() != LinePattern::Solid {
+            let (x0, y0, x1, y1) = (upper_left.0, upper_left.1, bottom_right.0, bottom_right.1);
+            let outline: Vec<Point> = [(x0, y0), (x0, y1), (x1, y1), (x1, y0), (x0, y0)]
+                .iter()
+                .map(|&p| make_point(p))
+                .collect();
+            self.stroke(&outline, style)?;
         } else {
             let color = make_piston_rgba(&style.color());
-            let [x0, y0, x1, y1] = make_point_pair(upper_left, bottom_right, self.scale);
-            line(
-                color,
-                self.scale,
+            let radius = make_stroke_radius(style);
+            let [x0, y0, x1, y1] = make_point_pair(upper_left, bottom_right);
+            let transform = self.context.transform;
+            for segment in [
                 [x0, y0, x0, y1],
-                self.context.transform,
-                self.graphics,
-            );
-            line(
-                color,
-                self.scale,
                 [x0, y1, x1, y1],
-                self.context.transform,
-                self.graphics,
-            );
-            line(
-                color,
-                self.scale,
                 [x1, y1, x1, y0],
-                self.context.transform,
-                self.graphics,
-            );
-            line(
-                color,
-                self.scale,
                 [x1, y0, x0, y0],
-                self.context.transform,
-                self.graphics,
-            );
+            ]
+            .iter()
+            {
+                self.batch.push_line(transform, color, radius, *segment);
+            }
+        }
+        Ok(())
+    }
+
+    fn draw_path<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        path: I,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let points: Vec<Point> = path.into_iter().map(make_point).collect();
+        self.stroke(&points, style)
+    }
+
+    fn fill_polygon<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        vert: I,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let color = make_piston_rgba(&style.color());
+        let points: Vec<Point> = vert.into_iter().map(make_point).collect();
+        let triangles = tessellate::fill_polygon(&points)
+            .map_err(|e| DrawingErrorKind::DrawingError(PistonBackendError::InvalidGeometry(e)))?;
+        self.sync_batch();
+        match self.fill_gradient.get() {
+            Some(gradient) => {
+                let (vertices, colors) = gradient.shade(&triangles, color);
+                self.batch
+                    .push_shaded_triangles(self.context.transform, &vertices, &colors);
+            }
+            None => self
+                .batch
+                .push_triangles(self.context.transform, color, &triangles),
         }
         Ok(())
     }
@@ -161,57 +371,283 @@ impl<'a, 'b> DrawingBackend for PistonBackend<'a, 'b> {
         style: &S,
         fill: bool,
     ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
-        let rect = circle(center.0 as f64, center.1 as f64, radius as f64);
+        self.sync_batch();
+        let color = make_piston_rgba(&style.color());
+        let center = [center.0 as f64, center.1 as f64];
         if fill {
-            ellipse(
-                make_piston_rgba(&style.color()),
-                rect,
-                self.context.transform,
-                self.graphics,
-            );
+            self.batch
+                .push_disc(self.context.transform, color, center, radius as f64);
         } else {
-            circle_arc(
-                make_piston_rgba(&style.color()),
-                self.scale,
-                std::f64::consts::PI,
-                0.0,
-                rect,
+            self.batch.push_ring(
                 self.context.transform,
-                self.graphics,
-            );
-            circle_arc(
-                make_piston_rgba(&style.color()),
-                self.scale,
-                0.0,
-                std::f64::consts::PI,
-                rect,
-                self.context.transform,
-                self.graphics,
+                color,
+                center,
+                radius as f64,
+                make_stroke_radius(style),
             );
         }
         Ok(())
     }
+
+    fn draw_text<TStyle: BackendTextStyle>(
+        &mut self,
+        text: &str,
+        style: &TStyle,
+        pos: BackendCoord,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let color = style.color();
+        if color.alpha == 0.0 || text.is_empty() {
+            return Ok(());
+        }
+
+        let ((min_x, min_y), (max_x, max_y)) = style
+            .layout_box(text)
+            .map_err(|e| DrawingErrorKind::FontError(Box::new(e)))?;
+        let (width, height) = (max_x - min_x, max_y - min_y);
+        let dx = match style.anchor().h_pos {
+            HPos::Left => 0,
+            HPos::Right => -width,
+            HPos::Center => -width / 2,
+        };
+        let dy = match style.anchor().v_pos {
+            VPos::Top => 0,
+            VPos::Center => -height / 2,
+            VPos::Bottom => -height,
+        };
+
+        let draw_state = self.draw_state();
+        let quads = match self.cache {
+            Some(ref mut cache) => cache.glyphs.run(&mut cache.texture_context, text, style)?,
+            None => None,
+        };
+        let quads = match quads {
+            Some(quads) => quads,
+            None => return self.draw_text_pixels(text, style, pos, (dx, dy), (min_x, min_y)),
+        };
+        let texture = match self.cache.as_ref().and_then(|cache| cache.glyphs.texture()) {
+            Some(texture) if !quads.is_empty() => texture,
+            // Only blank glyphs, such as spaces
+            _ => return Ok(()),
+        };
+        self.batch.flush(&self.batch_state, self.graphics);
+
+        let angle = match style.transform() {
+            FontTransform::None => 0.0,
+            FontTransform::Rotate90 => 90.0,
+            FontTransform::Rotate180 => 180.0,
+            FontTransform::Rotate270 => 270.0,
+        };
+        let transform = self
+            .context
+            .transform
+            .trans(pos.0 as f64, pos.1 as f64)
+            .rot_deg(angle)
+            .trans((dx - min_x) as f64, (dy - min_y) as f64);
+
+        self.graphics
+            .tri_list_uv(&draw_state, &make_piston_rgba(&color), texture, |f| {
+                for quad in &quads {
+                    f(
+                        &triangulation::rect_tri_list_xy(transform, quad.rect),
+                        &triangulation::rect_tri_list_uv(texture, quad.source),
+                    );
+                }
+            });
+        Ok(())
+    }
+
+    fn blit_bitmap(
+        &mut self,
+        pos: BackendCoord,
+        (iw, ih): (u32, u32),
+        src: &[u8],
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let draw_state = self.draw_state();
+        let cache = match self.cache {
+            Some(ref mut cache) => cache,
+            None => return self.blit_bitmap_pixels(pos, (iw, ih), src),
+        };
+        let texture = cache
+            .bitmaps
+            .upload(&mut cache.texture_context, (iw, ih), src)?;
+        self.batch.flush(&self.batch_state, self.graphics);
+
+        Image::new()
+            .rect(make_point_pair(pos, (iw as i32, ih as i32)))
+            .draw(texture, &draw_state, self.context.transform, self.graphics);
+        Ok(())
+    }
+
+    fn estimate_text_size<TStyle: BackendTextStyle>(
+        &self,
+        text: &str,
+        style: &TStyle,
+    ) -> Result<(u32, u32), DrawingErrorKind<Self::ErrorType>> {
+        let ((min_x, min_y), (max_x, max_y)) = style
+            .layout_box(text)
+            .map_err(|e| DrawingErrorKind::FontError(Box::new(e)))?;
+        Ok(((max_x - min_x) as u32, (max_y - min_y) as u32))
+    }
 }
 
+impl<'a, 'b, G: Graphics, F> Drop for PistonBackend<'a, 'b, G, F> {
+    fn drop(&mut self) {
+        // plotters does not call `present` when a chart goes out of scope
+        self.flush();
+    }
+}
+
+/// Result of the drawing closure for one frame, as returned by the `try_` loop functions.
+pub type DrawResult = Result<(), Box<dyn std::error::Error>>;
+
 #[allow(clippy::single_match)]
-pub fn draw_piston_window<F: FnOnce(PistonBackend) -> Result<(), Box<dyn std::error::Error>>>(
+fn next_frame<F: FnOnce(PistonBackend) -> DrawResult>(
     window: &mut PistonWindow,
+    mut cache: Option<&mut RenderCache>,
     draw: F,
-) -> Option<Event> {
-    if let Some(event) = window.next() {
-        window.draw_2d(&event, |c, g, _| match event {
-            Event::Loop(Loop::Render(arg)) => {
-                draw(PistonBackend::new(
-                    (arg.draw_size[0], arg.draw_size[1]),
-                    arg.window_size[0] / arg.draw_size[0] as f64,
-                    c,
-                    g,
-                ))
-                .ok();
+) -> Option<(Event, DrawResult)> {
+    let event = window.next()?;
+    let mut result = Ok(());
+    window.draw_2d(&event, |c, g, device| match event {
+        Event::Loop(Loop::Render(arg)) => {
+            let mut backend = PistonBackend::new(
+                (arg.draw_size[0], arg.draw_size[1]),
+                arg.window_size[0] / arg.draw_size[0] as f64,
+                c,
+                g,
+            );
+            if let Some(cache) = cache.as_mut() {
+                cache.begin_frame();
+                backend = backend.with_cache(cache);
             }
-            _ => {}
-        });
-        return Some(event);
-    }
-    None
+            result = draw(backend);
+            if let Some(cache) = cache {
+                cache.texture_context.encoder.flush(device);
+            }
+        }
+        _ => {}
+    });
+    Some((event, result))
+}
+
+/// Wait for the next event of `window` and, if it is a render event, draw a frame
+/// with `draw`. Errors returned by `draw` are discarded; use `try_draw_piston_window`
+/// to see them.
+pub fn draw_piston_window<F: FnOnce(PistonBackend) -> DrawResult>(
+    window: &mut PistonWindow,
+    draw: F,
+) -> Option<Event> {
+    next_frame(window, None, draw).map(|(event, _)| event)
+}
+
+/// Same as `draw_piston_window`, but keeps glyphs and other GPU resources in
+/// `cache` between frames, so text is drawn from textures instead of pixel by pixel.
+pub fn draw_piston_window_cached<F: FnOnce(PistonBackend) -> DrawResult>(
+    window: &mut PistonWindow,
+    cache: &mut RenderCache,
+    draw: F,
+) -> Option<Event> {
+    next_frame(window, Some(cache), draw).map(|(event, _)| event)
+}
+
+/// Same as `draw_piston_window`, but also returns what `draw` returned. Events
+/// that do not render a frame come with `Ok(())`.
+pub fn try_draw_piston_window<F: FnOnce(PistonBackend) -> DrawResult>(
+    window: &mut PistonWindow,
+    draw: F,
+) -> Option<(Event, DrawResult)> {
+    next_frame(window, None, draw)
+}
+
+/// Same as `draw_piston_window_cached`, but also returns what `draw` returned.
+pub fn try_draw_piston_window_cached<F: FnOnce(PistonBackend) -> DrawResult>(
+    window: &mut PistonWindow,
+    cache: &mut RenderCache,
+    draw: F,
+) -> Option<(Event, DrawResult)> {
+    next_frame(window, Some(cache), draw)
+}
+
+/// Same as `try_draw_piston_window_cached`, but splits the frame in a static part,
+/// drawn by `draw_static` into `layer` only when `key` or the window size changes,
+/// and a live part, drawn by `draw` over the layer every frame.
+///
+/// `draw` receives what `draw_static` returned, and should not clear the
+/// background, since that would hide the layer.
+pub fn try_draw_piston_window_layered<K, T, S, F>(
+    window: &mut PistonWindow,
+    cache: &mut RenderCache,
+    layer: &mut StaticLayer<K, T>,
+    key: K,
+    draw_static: S,
+    draw: F,
+) -> Option<(Event, DrawResult)>
+where
+    K: PartialEq,
+    S: FnOnce(PistonBackend) -> Result<T, Box<dyn std::error::Error>>,
+    F: FnOnce(PistonBackend, &T) -> DrawResult,
+{
+    let event = window.next()?;
+    let result = match event.render_args() {
+        Some(args) => draw_layered_frame(
+            window,
+            cache,
+            layer,
+            &args,
+            &mut (),
+            key,
+            |_, backend| draw_static(backend),
+            |_, backend, state| draw(backend, state),
+            |_, _| Ok(()),
+        ),
+        None => Ok(()),
+    };
+    Some((event, result))
+}
+
+/// Render one frame of a layered loop, with `overlay` drawn last over everything
+/// else. `app` is handed to every drawing step, so that they can share mutable
+/// state without several closures borrowing it.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn draw_layered_frame<A, K, T, S, F, O>(
+    window: &mut PistonWindow,
+    cache: &mut RenderCache,
+    layer: &mut StaticLayer<K, T>,
+    args: &RenderArgs,
+    app: &mut A,
+    key: K,
+    draw_static: S,
+    draw: F,
+    overlay: O,
+) -> DrawResult
+where
+    K: PartialEq,
+    S: FnOnce(&mut A, PistonBackend) -> Result<T, Box<dyn std::error::Error>>,
+    F: FnOnce(&mut A, PistonBackend, &T) -> DrawResult,
+    O: FnOnce(&mut A, PistonBackend) -> DrawResult,
+{
+    cache.begin_frame();
+    let mut result = layer.update(window, cache, args, key, |backend| {
+        draw_static(app, backend)
+    });
+    let event = Event::Loop(Loop::Render(*args));
+    let size = (args.draw_size[0], args.draw_size[1]);
+    let scale = args.window_size[0] / args.draw_size[0] as f64;
+    window.draw_2d(&event, |c, g, device| {
+        // The layer is opaque, but may be missing or smaller than the window
+        g.clear_color(LAYER_BACKGROUND);
+        layer.draw(&c, g);
+        let state = if result.is_ok() { layer.state() } else { None };
+        if let Some(state) = state {
+            let backend = PistonBackend::new(size, scale, c, g).with_cache(cache);
+            result = draw(app, backend, state);
+        }
+        let backend = PistonBackend::new(size, scale, c, g).with_cache(cache);
+        let overlay_result = overlay(app, backend);
+        if result.is_ok() {
+            result = overlay_result;
+        }
+        cache.texture_context.encoder.flush(device);
+    });
+    result
 }
@@ -0,0 +1,178 @@
+use std::f64::consts::PI;
+
+use piston_window::math::Matrix2d;
+use piston_window::triangulation::{tx, ty};
+use piston_window::{DrawState, Graphics, BACK_END_MAX_VERTEX_COUNT};
+
+/// Largest number of vertices handed to the graphics backend at once, kept a
+/// multiple of three so no triangle is split across two chunks.
+const CHUNK_VERTICES: usize = BACK_END_MAX_VERTEX_COUNT / 3 * 3;
+
+/// Coloured triangles gathered during a frame and submitted with a few
+/// `tri_list_c` calls.
+///
+/// Vertices are transformed to normalized device coordinates as they are pushed,
+/// and every vertex carries its own colour, so lines, rectangles and polygons of
+/// any colour end up in the same batch.
+#[derive(Default)]
+pub(crate) struct TriangleBatch {
+    positions: Vec<[f32; 2]>,
+    colors: Vec<[f32; 4]>,
+}
+
+impl TriangleBatch {
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    fn push_vertex(&mut self, transform: Matrix2d, color: [f32; 4], [x, y]: [f64; 2]) {
+        self.positions
+            .push([tx(transform, x, y), ty(transform, x, y)]);
+        self.colors.push(color);
+    }
+
+    pub fn push_triangle(&mut self, transform: Matrix2d, color: [f32; 4], triangle: [[f64; 2]; 3]) {
+        for vertex in triangle.iter() {
+            self.push_vertex(transform, color, *vertex);
+        }
+    }
+
+    /// Push a triangle list, three vertices per triangle.
+    pub fn push_triangles(&mut self, transform: Matrix2d, color: [f32; 4], vertices: &[[f32; 2]]) {
+        for &[x, y] in vertices {
+            self.push_vertex(transform, color, [x as f64, y as f64]);
+        }
+    }
+
+    /// Push a triangle list with a colour for every vertex.
+    pub fn push_shaded_triangles(
+        &mut self,
+        transform: Matrix2d,
+        vertices: &[[f32; 2]],
+        colors: &[[f32; 4]],
+    ) {
+        for (&[x, y], &color) in vertices.iter().zip(colors.iter()) {
+            self.push_vertex(transform, color, [x as f64, y as f64]);
+        }
+    }
+
+    /// Push a convex quad whose corners are given in order around it.
+    pub fn push_quad(&mut self, transform: Matrix2d, color: [f32; 4], [a, b, c, d]: [[f64; 2]; 4]) {
+        self.push_triangle(transform, color, [a, b, c]);
+        self.push_triangle(transform, color, [a, c, d]);
+    }
+
+    /// Push an axis aligned rectangle given as `[x, y, width, height]`.
+    pub fn push_rect(&mut self, transform: Matrix2d, color: [f32; 4], [x, y, w, h]: [f64; 4]) {
+        self.push_quad(
+            transform,
+            color,
+            [[x, y], [x + w, y], [x + w, y + h], [x, y + h]],
+        );
+    }
+
+    /// Push a line of the given radius with square caps, like `piston_window::line`.
+    pub fn push_line(
+        &mut self,
+        transform: Matrix2d,
+        color: [f32; 4],
+        radius: f64,
+        [x0, y0, x1, y1]: [f64; 4],
+    ) {
+        let (dx, dy) = (x1 - x0, y1 - y0);
+        let len = (dx * dx + dy * dy).sqrt();
+        if len == 0.0 {
+            self.push_rect(
+                transform,
+                color,
+                [x0 - radius, y0 - radius, 2.0 * radius, 2.0 * radius],
+            );
+            return;
+        }
+        let (ux, uy) = (dx / len * radius, dy / len * radius);
+        let (nx, ny) = (-uy, ux);
+        self.push_quad(
+            transform,
+            color,
+            [
+                [x0 - ux + nx, y0 - uy + ny],
+                [x0 - ux - nx, y0 - uy - ny],
+                [x1 + ux - nx, y1 + uy - ny],
+                [x1 + ux + nx, y1 + uy + ny],
+            ],
+        );
+    }
+
+    /// Push a filled circle.
+    pub fn push_disc(
+        &mut self,
+        transform: Matrix2d,
+        color: [f32; 4],
+        center: [f64; 2],
+        radius: f64,
+    ) {
+        let points = circle_points(center, radius, circle_segments(radius));
+        for pair in points.windows(2) {
+            self.push_triangle(transform, color, [center, pair[0], pair[1]]);
+        }
+    }
+
+    /// Push the outline of a circle, `stroke` being the line radius.
+    pub fn push_ring(
+        &mut self,
+        transform: Matrix2d,
+        color: [f32; 4],
+        center: [f64; 2],
+        radius: f64,
+        stroke: f64,
+    ) {
+        // Both rings need the same number of points to be joined into quads
+        let segments = circle_segments(radius + stroke);
+        let outer = circle_points(center, radius + stroke, segments);
+        let inner = circle_points(center, (radius - stroke).max(0.0), segments);
+        for i in 0..outer.len() - 1 {
+            self.push_quad(
+                transform,
+                color,
+                [outer[i], outer[i + 1], inner[i + 1], inner[i]],
+            );
+        }
+    }
+
+    /// Submit everything pushed so far and empty the batch.
+    pub fn flush<G: Graphics>(&mut self, draw_state: &DrawState, graphics: &mut G) {
+        if self.is_empty() {
+            return;
+        }
+        let (positions, colors) = (&self.positions, &self.colors);
+        graphics.tri_list_c(draw_state, |f| {
+            for (positions, colors) in positions
+                .chunks(CHUNK_VERTICES)
+                .zip(colors.chunks(CHUNK_VERTICES))
+            {
+                f(positions, colors)
+            }
+        });
+        self.positions.clear();
+        self.colors.clear();
+    }
+}
+
+/// Number of segments for a smooth circle of `radius` pixels.
+fn circle_segments(radius: f64) -> usize {
+    (radius * PI / 2.0).ceil().clamp(16.0, 128.0) as usize
+}
+
+/// `segments + 1` points around a circle, closed so that the last point repeats
+/// the first one.
+fn circle_points(center: [f64; 2], radius: f64, segments: usize) -> Vec<[f64; 2]> {
+    (0..=segments)
+        .map(|i| {
+            let angle = 2.0 * PI * i as f64 / segments as f64;
+            [
+                center[0] + radius * angle.cos(),
+                center[1] + radius * angle.sin(),
+            ]
+        })
+        .collect()
+}
@@ -0,0 +1,96 @@
+use image::RgbaImage;
+use plotters_backend::DrawingErrorKind;
+use texture::{CreateTexture, UpdateTexture};
+
+use crate::cache::{create_texture, update_texture};
+use crate::error::PistonBackendError;
+
+/// Number of frames a bitmap texture may stay unused before it is released.
+const BITMAP_RETENTION_FRAMES: u64 = 60;
+
+struct BitmapSlot<T> {
+    size: (u32, u32),
+    texture: T,
+    last_used: u64,
+}
+
+/// Pool of textures used by `blit_bitmap`.
+///
+/// Waterfalls and heatmaps usually blit a bitmap of the same size every frame, so
+/// textures are matched by size and updated in place instead of being recreated.
+/// Updates go through the texture context encoder, which has to be flushed before
+/// the frame is submitted.
+pub(crate) struct BitmapCache<T> {
+    slots: Vec<BitmapSlot<T>>,
+    frame: u64,
+}
+
+impl<T> Default for BitmapCache<T> {
+    fn default() -> Self {
+        Self {
+            slots: Vec::new(),
+            frame: 0,
+        }
+    }
+}
+
+impl<T> BitmapCache<T> {
+    pub fn begin_frame(&mut self) {
+        self.frame += 1;
+        let frame = self.frame;
+        self.slots
+            .retain(|slot| frame - slot.last_used <= BITMAP_RETENTION_FRAMES);
+    }
+
+    /// Upload an RGB buffer and return the texture holding it for this frame.
+    pub fn upload<F>(
+        &mut self,
+        texture_context: &mut F,
+        size: (u32, u32),
+        src: &[u8],
+    ) -> Result<&T, DrawingErrorKind<PistonBackendError>>
+    where
+        T: CreateTexture<F> + UpdateTexture<F>,
+    {
+        let pixels = size.0 as usize * size.1 as usize;
+        if src.len() < pixels * 3 {
+            return Err(DrawingErrorKind::DrawingError(
+                PistonBackendError::BitmapSize {
+                    width: size.0,
+                    height: size.1,
+                    len: src.len(),
+                },
+            ));
+        }
+        let mut rgba = Vec::with_capacity(pixels * 4);
+        for rgb in src[..pixels * 3].chunks(3) {
+            rgba.extend_from_slice(rgb);
+            rgba.push(255);
+        }
+        let image = RgbaImage::from_raw(size.0, size.1, rgba).expect("buffer matches bitmap size");
+
+        let frame = self.frame;
+        let free = self
+            .slots
+            .iter()
+            .position(|slot| slot.size == size && slot.last_used != frame);
+        let slot = match free {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                update_texture(&mut slot.texture, texture_context, &image, [0, 0], "bitmap")?;
+                slot
+            }
+            None => {
+                let texture = create_texture(texture_context, &image, "bitmap")?;
+                self.slots.push(BitmapSlot {
+                    size,
+                    texture,
+                    last_used: frame,
+                });
+                self.slots.last_mut().unwrap()
+            }
+        };
+        slot.last_used = frame;
+        Ok(&slot.texture)
+    }
+}
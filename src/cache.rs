@@ -0,0 +1,95 @@
+use image::RgbaImage;
+use piston_window::{G2dTexture, G2dTextureContext, PistonWindow};
+use plotters_backend::DrawingErrorKind;
+use texture::{CreateTexture, Format, TextureSettings, UpdateTexture};
+
+use crate::bitmap::BitmapCache;
+use crate::error::PistonBackendError;
+use crate::text::GlyphCache;
+
+/// GPU resources that outlive a single frame of a `draw_piston_window_cached` loop.
+///
+/// Create one per window, next to the `PistonWindow` itself, and pass it to
+/// every frame. `T` is the texture type of the piston graphics backend and `F`
+/// the factory its textures are created with; the defaults are those of
+/// `PistonWindow`.
+pub struct RenderCache<T = G2dTexture, F = G2dTextureContext> {
+    pub(crate) texture_context: F,
+    pub(crate) glyphs: GlyphCache<T>,
+    pub(crate) bitmaps: BitmapCache<T>,
+}
+
+impl RenderCache {
+    pub fn new(window: &mut PistonWindow) -> Self {
+        Self::with_texture_context(window.create_texture_context())
+    }
+}
+
+impl<T, F> RenderCache<T, F> {
+    /// Cache for another piston graphics backend, for example
+    /// `RenderCache::<opengl_graphics::Texture, ()>::with_texture_context(())`.
+    pub fn with_texture_context(texture_context: F) -> Self {
+        Self {
+            texture_context,
+            glyphs: GlyphCache::new(),
+            bitmaps: BitmapCache::default(),
+        }
+    }
+
+    /// Make the glyph atlas `size` pixels wide and high, instead of
+    /// `DEFAULT_ATLAS_SIZE`. Four bytes of GPU memory are used per pixel.
+    pub fn glyph_atlas_size(mut self, size: u32) -> Self {
+        self.glyphs.set_atlas_size(size);
+        self
+    }
+
+    pub fn glyphs(&self) -> &GlyphCache<T> {
+        &self.glyphs
+    }
+
+    pub fn texture_context(&mut self) -> &mut F {
+        &mut self.texture_context
+    }
+
+    /// Age the cached textures by one frame. The `draw_piston_window` loops call
+    /// this themselves; custom loops call it once before each frame.
+    pub fn begin_frame(&mut self) {
+        self.glyphs.begin_frame();
+        self.bitmaps.begin_frame();
+    }
+}
+
+pub(crate) fn create_texture<T: CreateTexture<F>, F>(
+    texture_context: &mut F,
+    image: &RgbaImage,
+    target: &'static str,
+) -> Result<T, DrawingErrorKind<PistonBackendError>> {
+    let (width, height) = image.dimensions();
+    T::create(
+        texture_context,
+        Format::Rgba8,
+        image,
+        [width, height],
+        &TextureSettings::new(),
+    )
+    .map_err(|e| DrawingErrorKind::DrawingError(PistonBackendError::texture_upload(target, e)))
+}
+
+pub(crate) fn update_texture<T: UpdateTexture<F>, F>(
+    texture: &mut T,
+    texture_context: &mut F,
+    image: &RgbaImage,
+    offset: [u32; 2],
+    target: &'static str,
+) -> Result<(), DrawingErrorKind<PistonBackendError>> {
+    let (width, height) = image.dimensions();
+    texture
+        .update(
+            texture_context,
+            Format::Rgba8,
+            image,
+            offset,
+            [width, height],
+        )
+        .map_err(|e| DrawingErrorKind::DrawingError(PistonBackendError::texture_upload(target, e)))
+}
@@ -0,0 +1,71 @@
+use std::cell::Cell;
+use std::ops::Range;
+use std::rc::Rc;
+
+/// Shared handle to the clip rectangle of a `PistonBackend`.
+///
+/// plotters does not tell the backend which drawing area an element belongs to,
+/// so the clip rectangle is set through this handle, usually to the pixel range
+/// of a chart's plotting area, around the drawing calls it should apply to:
+///
+/// ```ignore
+/// let clip = backend.clip();
+/// let root = backend.into_drawing_area();
+/// // ...
+/// clip.clipped(chart.plotting_area().get_pixel_range(), || {
+///     chart.draw_series(LineSeries::new(spectrum, &RED))
+/// })?;
+/// ```
+///
+/// Nothing is drawn outside the rectangle. It is applied with the scissor of
+/// the piston draw state, in backend pixels.
+#[derive(Clone, Debug, Default)]
+pub struct ClipHandle(Rc<Cell<Option<[i32; 4]>>>);
+
+impl ClipHandle {
+    /// The clip rectangle as the pixel ranges along x and y.
+    pub fn get(&self) -> Option<(Range<i32>, Range<i32>)> {
+        self.0.get().map(|[x0, y0, x1, y1]| (x0..x1, y0..y1))
+    }
+
+    /// Clip to the pixel ranges along x and y, as returned by
+    /// `DrawingArea::get_pixel_range`, or stop clipping.
+    pub fn set(&self, area: Option<(Range<i32>, Range<i32>)>) {
+        self.0
+            .set(area.map(|(x, y)| [x.start, y.start, x.end, y.end]));
+    }
+
+    /// Run `draw` clipped to `area`, within the current clip rectangle if there is
+    /// one, and restore that rectangle afterwards.
+    pub fn clipped<T, F: FnOnce() -> T>(&self, area: (Range<i32>, Range<i32>), draw: F) -> T {
+        let previous = self.0.get();
+        let [mut x0, mut y0, mut x1, mut y1] = [area.0.start, area.1.start, area.0.end, area.1.end];
+        if let Some([px0, py0, px1, py1]) = previous {
+            x0 = x0.max(px0);
+            y0 = y0.max(py0);
+            x1 = x1.min(px1);
+            y1 = y1.min(py1);
+        }
+        self.0.set(Some([x0, y0, x1, y1]));
+        let result = draw();
+        self.0.set(previous);
+        result
+    }
+
+    /// The clip rectangle as a scissor `[x, y, width, height]`.
+    pub(crate) fn scissor(&self) -> Option<[u32; 4]> {
+        self.0.get().map(|[x0, y0, x1, y1]| {
+            let (x0, y0) = (x0.max(0), y0.max(0));
+            let (x1, y1) = (x1.max(x0), y1.max(y0));
+            [x0 as u32, y0 as u32, (x1 - x0) as u32, (y1 - y0) as u32]
+        })
+    }
+}
+
+/// Intersection of two scissor rectangles `[x, y, width, height]`.
+pub(crate) fn intersect_scissors(a: [u32; 4], b: [u32; 4]) -> [u32; 4] {
+    let (x0, y0) = (a[0].max(b[0]), a[1].max(b[1]));
+    let x1 = (a[0] + a[2]).min(b[0] + b[2]).max(x0);
+    let y1 = (a[1] + a[3]).min(b[1] + b[3]).max(y0);
+    [x0, y0, x1 - x0, y1 - y0]
+}
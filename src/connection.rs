@@ -0,0 +1,231 @@
+use std::io::{self, Read};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use serialport::SerialPort;
+
+use crate::diagnostics::SourceCounters;
+use crate::discovery::{Discovery, SerialDevice};
+use crate::serial::{SerialSource, SourceError, AUTO_PORT};
+
+/// How long to wait between attempts to reopen a disconnected device.
+const RETRY_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Samples missing from a source while its device was disconnected.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gap {
+    /// When the device was lost.
+    pub lost_at: SystemTime,
+    /// How long it took to reopen the device.
+    pub duration: Duration,
+    /// Number of samples received before the device was lost.
+    pub samples_before: u64,
+}
+
+/// Item sent by the thread reading a source to the viewer, so that a gap
+/// appears in the stream where the samples are missing.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SourceEvent<T> {
+    Sample(T),
+    Gap(Gap),
+}
+
+/// Outcome of `SerialConnection::read`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Received {
+    /// This many bytes were read into the buffer.
+    Bytes(usize),
+    /// No data arrived within the timeout of the source.
+    TimedOut,
+    /// The device was lost and opened again. Bytes of a line or frame read
+    /// before the gap do not continue after it.
+    Reconnected(Gap),
+}
+
+/// A serial port that is opened again when its device disappears, as when the
+/// USB cable is unplugged or the board resets.
+///
+/// The device is matched by its USB serial number when it has one, because it
+/// may come back on another port, and by the port name otherwise. While it is
+/// away the `SourceCounters` report the source as disconnected.
+///
+/// Devices are listed and opened through `P`, which is `SystemPorts` except in
+/// tests.
+///
+/// ```ignore
+/// let mut connection = SerialConnection::open(source, counters)?;
+/// loop {
+///     match connection.read(&mut buffer) {
+///         Received::Bytes(n) => parser.push(&buffer[..n]),
+///         Received::TimedOut => {}
+///         Received::Reconnected(gap) => {
+///             parser.reset();
+///             sender.send(SourceEvent::Gap(gap))?;
+///         }
+///     }
+/// }
+/// ```
+pub struct SerialConnection<P: PortAccess = SystemPorts> {
+    ports: P,
+    source: SerialSource,
+    serial_number: Option<String>,
+    port: Option<P::Port>,
+    counters: SourceCounters,
+    retry_interval: Duration,
+}
+
+/// How a `SerialConnection` lists the USB serial devices and opens its port.
+pub trait PortAccess {
+    type Port: Read;
+
+    /// The USB serial devices connected now.
+    fn scan(&mut self) -> Result<Vec<SerialDevice>, SourceError>;
+
+    /// Open the port of `source`, whose port name is never `AUTO_PORT`.
+    fn open(&mut self, source: &SerialSource) -> Result<Self::Port, SourceError>;
+}
+
+/// The serial ports of this machine.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemPorts;
+
+impl PortAccess for SystemPorts {
+    type Port = Box<dyn SerialPort>;
+
+    fn scan(&mut self) -> Result<Vec<SerialDevice>, SourceError> {
+        SerialDevice::scan()
+    }
+
+    fn open(&mut self, source: &SerialSource) -> Result<Self::Port, SourceError> {
+        source.open()
+    }
+}
+
+impl SerialConnection {
+    /// Open the port of `source`, which has to be connected now.
+    pub fn open(source: SerialSource, counters: SourceCounters) -> Result<Self, SourceError> {
+        Self::open_with(SystemPorts, source, counters)
+    }
+}
+
+impl<P: PortAccess> SerialConnection<P> {
+    /// Open the port of `source` through `ports`.
+    pub fn open_with(
+        mut ports: P,
+        source: SerialSource,
+        counters: SourceCounters,
+    ) -> Result<Self, SourceError> {
+        let auto = source.port_name() == AUTO_PORT;
+        let devices = if auto {
+            ports.scan()?
+        } else {
+            ports.scan().unwrap_or_default()
+        };
+        let port_name = if auto {
+            Discovery::from_devices(devices.clone()).port_name()?
+        } else {
+            source.port_name().to_owned()
+        };
+        let source = source.port(port_name.as_str());
+        let port = ports.open(&source)?;
+        let serial_number = devices
+            .into_iter()
+            .find(|device| device.port_name == port_name)
+            .and_then(|device| device.serial_number);
+        Ok(Self {
+            ports,
+            source,
+            serial_number,
+            port: Some(port),
+            counters,
+            retry_interval: RETRY_INTERVAL,
+        })
+    }
+
+    /// How long to wait between attempts to reopen a disconnected device.
+    pub fn retry_interval(mut self, interval: Duration) -> Self {
+        self.retry_interval = interval;
+        self
+    }
+
+    /// Name of the port currently open, which changes when the device comes
+    /// back on another port.
+    pub fn port_name(&self) -> &str {
+        self.source.port_name()
+    }
+
+    pub fn serial_number(&self) -> Option<&str> {
+        self.serial_number.as_deref()
+    }
+
+    /// Read from the port into `buffer`. When the device is lost, block until it
+    /// is back and report the gap.
+    ///
+    /// Only a hang-up reconnects. An interrupted read is retried, and other
+    /// errors, such as `WouldBlock` or a framing error, leave the port open and
+    /// are reported as `TimedOut`, so that the caller reads again.
+    pub fn read(&mut self, buffer: &mut [u8]) -> Received {
+        loop {
+            let result = match self.port.as_mut() {
+                Some(port) => port.read(buffer),
+                None => Err(io::ErrorKind::NotConnected.into()),
+            };
+            return match result {
+                // A port that has been hung up reads as the end of the file
+                Ok(0) if !buffer.is_empty() => Received::Reconnected(self.reconnect()),
+                Ok(n) => Received::Bytes(n),
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(ref e) if is_hang_up(e) => Received::Reconnected(self.reconnect()),
+                Err(_) => Received::TimedOut,
+            };
+        }
+    }
+
+    fn reconnect(&mut self) -> Gap {
+        self.port = None;
+        let lost_at = SystemTime::now();
+        let since = Instant::now();
+        let (samples_before, _, _) = self.counters.totals();
+        self.counters.disconnected(since);
+
+        while self.port.is_none() {
+            thread::sleep(self.retry_interval);
+            self.port = self.reopen();
+        }
+
+        let gap = Gap {
+            lost_at,
+            duration: since.elapsed(),
+            samples_before,
+        };
+        self.counters.reconnected(gap.clone());
+        gap
+    }
+
+    fn reopen(&mut self) -> Option<P::Port> {
+        if let Some(serial_number) = &self.serial_number {
+            let device = self
+                .ports
+                .scan()
+                .ok()?
+                .into_iter()
+                .find(|device| device.serial_number.as_ref() == Some(serial_number))?;
+            self.source = self.source.clone().port(device.port_name);
+        }
+        self.ports.open(&self.source).ok()
+    }
+}
+
+/// Whether `error` means that the device is gone. serialport reports a hang-up
+/// as `BrokenPipe` and a missing device as `NotFound`.
+fn is_hang_up(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::BrokenPipe
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::NotFound
+            | io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+    )
+}
@@ -0,0 +1,275 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use plotters_backend::{BackendTextStyle, DrawingBackend, DrawingErrorKind};
+
+use crate::connection::Gap;
+use crate::panel::{draw_panel, Corner};
+
+/// Shortest time over which rates are measured.
+const RATE_PERIOD: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Default)]
+struct Counters {
+    received: AtomicU64,
+    parse_errors: AtomicU64,
+    dropped: AtomicU64,
+    lost_frames: AtomicU64,
+    connection: Mutex<Connection>,
+}
+
+#[derive(Debug, Default)]
+struct Connection {
+    lost_since: Option<Instant>,
+    gaps: Vec<Gap>,
+}
+
+/// Counters of a data source, updated by the thread reading it and shown by
+/// `Diagnostics`.
+#[derive(Clone, Debug, Default)]
+pub struct SourceCounters(Arc<Counters>);
+
+impl SourceCounters {
+    /// Count `samples` samples read from the source.
+    pub fn received(&self, samples: u64) {
+        self.0.received.fetch_add(samples, Ordering::Relaxed);
+    }
+
+    /// Count data that could not be parsed into a sample.
+    pub fn parse_error(&self) {
+        self.0.parse_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count `samples` samples read but thrown away, for example because the
+    /// channel to the viewer was full.
+    pub fn dropped(&self, samples: u64) {
+        self.0.dropped.fetch_add(samples, Ordering::Relaxed);
+    }
+
+    /// Count `frames` frames of a framed protocol that never arrived or arrived
+    /// corrupted.
+    pub fn lost_frames(&self, frames: u64) {
+        self.0.lost_frames.fetch_add(frames, Ordering::Relaxed);
+    }
+
+    /// Report the device of the source as lost since `since`.
+    pub fn disconnected(&self, since: Instant) {
+        self.connection().lost_since = Some(since);
+    }
+
+    /// Report the device of the source as back, after `gap`.
+    pub fn reconnected(&self, gap: Gap) {
+        let mut connection = self.connection();
+        connection.lost_since = None;
+        connection.gaps.push(gap);
+    }
+
+    /// Since when the device of the source is lost, or `None` while it is
+    /// connected.
+    pub fn disconnected_since(&self) -> Option<Instant> {
+        self.connection().lost_since
+    }
+
+    /// Gaps in the samples so far, oldest first.
+    pub fn gaps(&self) -> Vec<Gap> {
+        self.connection().gaps.clone()
+    }
+
+    fn connection(&self) -> MutexGuard<'_, Connection> {
+        // The state stays consistent even if a thread panicked holding the lock
+        self.0
+            .connection
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Totals so far, as `(received, parse_errors, dropped)`.
+    pub fn totals(&self) -> (u64, u64, u64) {
+        (
+            self.0.received.load(Ordering::Relaxed),
+            self.0.parse_errors.load(Ordering::Relaxed),
+            self.0.dropped.load(Ordering::Relaxed),
+        )
+    }
+}
+
+/// Events per second, averaged over `RATE_PERIOD`.
+#[derive(Debug, Default)]
+struct RateMeter {
+    since: Option<Instant>,
+    count: u64,
+    rate: Option<f64>,
+}
+
+impl RateMeter {
+    fn add(&mut self, count: u64, now: Instant) {
+        let since = *self.since.get_or_insert(now);
+        self.count += count;
+        let elapsed = now.saturating_duration_since(since);
+        if elapsed >= RATE_PERIOD {
+            self.rate = Some(self.count as f64 / elapsed.as_secs_f64());
+            self.since = Some(now);
+            self.count = 0;
+        }
+    }
+}
+
+/// Health of a live viewer: render frame rate, input sample rate, channel
+/// backlog, connection state and gaps, parse errors, dropped samples, lost
+/// frames and processing time, drawn as a panel in the top right corner while
+/// it is visible.
+///
+/// The thread reading the source updates the `SourceCounters` returned by
+/// `counters`. The viewer calls `frame` once per frame, reports the backlog it
+/// found with `set_backlog`, and runs its processing through `measure`.
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    counters: SourceCounters,
+    visible: bool,
+    frames: RateMeter,
+    samples: RateMeter,
+    received: u64,
+    backlog: Option<(usize, usize)>,
+    processing: Option<Duration>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handle to the counters of the data source, to move into the thread reading it.
+    pub fn counters(&self) -> SourceCounters {
+        self.counters.clone()
+    }
+
+    pub fn visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    /// Count a drawn frame, and sample the counters for the input rate.
+    pub fn frame(&mut self, now: Instant) {
+        self.frames.add(1, now);
+        let (received, _, _) = self.counters.totals();
+        self.samples
+            .add(received.saturating_sub(self.received), now);
+        self.received = received;
+    }
+
+    /// Number of samples that were waiting in a channel of `capacity` samples.
+    pub fn set_backlog(&mut self, pending: usize, capacity: usize) {
+        self.backlog = Some((pending, capacity));
+    }
+
+    /// Run `process`, usually the DSP of one update, and keep how long it took.
+    pub fn measure<T, F: FnOnce() -> T>(&mut self, process: F) -> T {
+        let start = Instant::now();
+        let result = process();
+        self.processing = Some(start.elapsed());
+        result
+    }
+
+    /// Frames drawn per second, once measured.
+    pub fn fps(&self) -> Option<f64> {
+        self.frames.rate
+    }
+
+    /// Samples received per second, once measured.
+    pub fn sample_rate(&self) -> Option<f64> {
+        self.samples.rate
+    }
+
+    /// The rows of the panel, as pairs of a label and a value.
+    pub fn lines(&self) -> Vec<(&'static str, String)> {
+        let unknown = || "-".to_string();
+        let (_, parse_errors, dropped) = self.counters.totals();
+        vec![
+            (
+                "FPS",
+                self.fps().map_or_else(unknown, |fps| format!("{:.1}", fps)),
+            ),
+            (
+                "Sample rate",
+                self.sample_rate()
+                    .map_or_else(unknown, |rate| format!("{:.0} Hz", rate)),
+            ),
+            (
+                "Backlog",
+                self.backlog.map_or_else(unknown, |(pending, capacity)| {
+                    let fill = 100.0 * pending as f64 / capacity.max(1) as f64;
+                    format!("{} / {} ({:.0}%)", pending, capacity, fill)
+                }),
+            ),
+            (
+                "Connection",
+                self.counters.disconnected_since().map_or_else(
+                    || "connected".to_string(),
+                    |since| format!("lost for {} s", since.elapsed().as_secs()),
+                ),
+            ),
+            ("Gaps", {
+                let gaps = self.counters.gaps();
+                let total: Duration = gaps.iter().map(|gap| gap.duration).sum();
+                format!("{} ({:.1} s)", gaps.len(), total.as_secs_f64())
+            }),
+            ("Parse errors", parse_errors.to_string()),
+            ("Dropped samples", dropped.to_string()),
+            (
+                "Lost frames",
+                self.counters
+                    .0
+                    .lost_frames
+                    .load(Ordering::Relaxed)
+                    .to_string(),
+            ),
+            (
+                "DSP time",
+                self.processing.map_or_else(unknown, |time| {
+                    format!("{:.2} ms", time.as_secs_f64() * 1000.0)
+                }),
+            ),
+        ]
+    }
+
+    /// Draw the panel in the top right corner of `backend` while it is visible,
+    /// with the text in `style`.
+    pub fn draw<DB: DrawingBackend, S: BackendTextStyle>(
+        &self,
+        backend: &mut DB,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<DB::ErrorType>> {
+        if !self.visible {
+            return Ok(());
+        }
+        draw_panel(backend, style, Corner::TopRight, &self.lines())
+    }
+
+    /// Draw a notice in the bottom left corner of `backend` while the device of
+    /// the source is lost, whether the panel is visible or not.
+    pub fn draw_connection<DB: DrawingBackend, S: BackendTextStyle>(
+        &self,
+        backend: &mut DB,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<DB::ErrorType>> {
+        let since = match self.counters.disconnected_since() {
+            Some(since) => since,
+            None => return Ok(()),
+        };
+        let waiting = format!("waiting for the device ({} s)", since.elapsed().as_secs());
+        draw_panel(
+            backend,
+            style,
+            Corner::BottomLeft,
+            &[("Disconnected", waiting)],
+        )
+    }
+}
@@ -0,0 +1,219 @@
+use std::fmt;
+use std::io;
+
+use serialport::{available_ports, ErrorKind, SerialPortInfo, SerialPortType, UsbPortInfo};
+
+use crate::serial::{SerialSource, SourceError};
+
+/// USB identity of a kind of board, or of the USB serial adapter it is built
+/// around.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoardSignature {
+    pub name: &'static str,
+    pub vid: u16,
+    /// `None` matches every product of the vendor.
+    pub pid: Option<u16>,
+}
+
+impl BoardSignature {
+    pub fn matches(&self, vid: u16, pid: u16) -> bool {
+        self.vid == vid && self.pid.unwrap_or(pid) == pid
+    }
+}
+
+/// The boards used with the viewers, most specific first: the OpenBCI dongle is
+/// built on an FTDI chip, so it has to be tried before FTDI adapters in general.
+pub const KNOWN_BOARDS: &[BoardSignature] = &[
+    BoardSignature {
+        name: "OpenBCI dongle",
+        vid: 0x0403,
+        pid: Some(0x6015),
+    },
+    BoardSignature {
+        name: "Arduino (native USB ACM)",
+        vid: 0x2341,
+        pid: None,
+    },
+    BoardSignature {
+        name: "Arduino (native USB ACM)",
+        vid: 0x2a03,
+        pid: None,
+    },
+    BoardSignature {
+        name: "Arduino Uno/Nano clone (CH340)",
+        vid: 0x1a86,
+        pid: None,
+    },
+    BoardSignature {
+        name: "FTDI USB serial",
+        vid: 0x0403,
+        pid: None,
+    },
+];
+
+/// A USB serial port, with the known board it looks like.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SerialDevice {
+    pub port_name: String,
+    pub vid: u16,
+    pub pid: u16,
+    pub serial_number: Option<String>,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+    /// `None` when no signature of `KNOWN_BOARDS` matches.
+    pub board: Option<BoardSignature>,
+}
+
+impl SerialDevice {
+    pub fn new(port_name: String, info: UsbPortInfo) -> Self {
+        Self {
+            board: KNOWN_BOARDS
+                .iter()
+                .find(|board| board.matches(info.vid, info.pid))
+                .copied(),
+            port_name,
+            vid: info.vid,
+            pid: info.pid,
+            serial_number: info.serial_number,
+            manufacturer: info.manufacturer,
+            product: info.product,
+        }
+    }
+
+    /// The USB serial ports among `ports`; other kinds of port are left out.
+    pub fn from_ports<I: IntoIterator<Item = SerialPortInfo>>(ports: I) -> Vec<Self> {
+        ports
+            .into_iter()
+            .filter_map(|port| match port.port_type {
+                SerialPortType::UsbPort(info) => Some(Self::new(port.port_name, info)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The USB serial ports of this machine.
+    pub fn scan() -> Result<Vec<Self>, SourceError> {
+        let ports = available_ports().map_err(SourceError::PortList)?;
+        Ok(Self::from_ports(ports))
+    }
+
+    /// The default settings, on the port of this device.
+    pub fn source(&self) -> SerialSource {
+        SerialSource::new(self.port_name.as_str())
+    }
+
+    /// Why the port cannot be opened right now, by trying to open it, or `None`
+    /// when it can.
+    ///
+    /// Opening the port toggles DTR, which resets boards such as the Arduino
+    /// Uno, and briefly holds it, so only probe a port about to be used.
+    pub fn problem(&self) -> Option<PortProblem> {
+        self.source().open().err().map(|error| match error {
+            SourceError::Serial { error, .. } => PortProblem::of(&error),
+            error => PortProblem::Other(error.to_string()),
+        })
+    }
+}
+
+impl fmt::Display for SerialDevice {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{} ({:04x}:{:04x}", self.port_name, self.vid, self.pid)?;
+        if let Some(board) = &self.board {
+            write!(fmt, ", {}", board.name)?;
+        }
+        if let Some(serial_number) = &self.serial_number {
+            write!(fmt, ", serial number {}", serial_number)?;
+        }
+        write!(fmt, ")")
+    }
+}
+
+/// Outcome of looking for the boards of `KNOWN_BOARDS` among the serial ports.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Discovery {
+    NotFound,
+    Found(SerialDevice),
+    /// More than one board is connected, and one has to be chosen.
+    Several(Vec<SerialDevice>),
+}
+
+impl Discovery {
+    /// Look for known boards among the serial ports of this machine.
+    pub fn scan() -> Result<Self, SourceError> {
+        Ok(Self::from_devices(SerialDevice::scan()?))
+    }
+
+    /// Look for known boards among `devices`.
+    pub fn from_devices(devices: Vec<SerialDevice>) -> Self {
+        let names: Vec<String> = devices.iter().map(|d| d.port_name.clone()).collect();
+        let mut boards: Vec<SerialDevice> = devices
+            .into_iter()
+            .filter(|device| device.board.is_some())
+            // macOS lists every device twice, as /dev/cu.* and /dev/tty.*
+            .filter(|device| match device.port_name.strip_prefix("/dev/tty.") {
+                Some(name) => !names.contains(&format!("/dev/cu.{}", name)),
+                None => true,
+            })
+            .collect();
+        match boards.len() {
+            0 => Discovery::NotFound,
+            1 => Discovery::Found(boards.remove(0)),
+            _ => Discovery::Several(boards),
+        }
+    }
+
+    /// The port of the board found, or why there is not exactly one.
+    pub(crate) fn port_name(self) -> Result<String, SourceError> {
+        match self {
+            Discovery::Found(device) => Ok(device.port_name),
+            Discovery::NotFound => Err(SourceError::NoBoard),
+            Discovery::Several(devices) => Err(SourceError::SeveralBoards(
+                devices.into_iter().map(|device| device.port_name).collect(),
+            )),
+        }
+    }
+}
+
+/// Why a serial port could not be opened.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PortProblem {
+    /// The user may not open the port.
+    PermissionDenied,
+    /// Another program, such as a serial monitor, has the port open, or the
+    /// device went away: serialport reports both the same way.
+    Unavailable,
+    /// There is no device behind the port any more.
+    NotFound,
+    Other(String),
+}
+
+impl PortProblem {
+    pub fn of(error: &serialport::Error) -> Self {
+        match error.kind() {
+            ErrorKind::Io(io::ErrorKind::PermissionDenied) => PortProblem::PermissionDenied,
+            // Reported for a port locked by another process (EBUSY, or a
+            // held flock), but also for a device unplugged as it is opened
+            ErrorKind::NoDevice => PortProblem::Unavailable,
+            ErrorKind::Io(io::ErrorKind::NotFound) => PortProblem::NotFound,
+            _ => PortProblem::Other(error.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for PortProblem {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PortProblem::PermissionDenied => write!(
+                fmt,
+                "permission denied (on Linux, add the user to the group owning the port, \
+                 usually dialout or uucp)"
+            ),
+            PortProblem::Unavailable => write!(
+                fmt,
+                "the port is in use by another program, or the device was disconnected"
+            ),
+            PortProblem::NotFound => write!(fmt, "the device is not connected"),
+            PortProblem::Other(reason) => write!(fmt, "{}", reason),
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+
+use lyon_tessellation::TessellationError;
+
+/// Errors reported by `PistonBackend` and the capture helpers.
+///
+/// Font layout and rasterization failures coming from the plotters text style are
+/// reported by plotters itself as `DrawingErrorKind::FontError`.
+#[derive(Debug)]
+pub enum PistonBackendError {
+    /// A texture could not be created or updated on the GPU.
+    TextureUpload {
+        target: &'static str,
+        reason: String,
+    },
+    /// A path or polygon could not be tessellated into triangles.
+    InvalidGeometry(TessellationError),
+    /// The buffer handed to `blit_bitmap` is shorter than its declared size.
+    BitmapSize { width: u32, height: u32, len: usize },
+    /// A captured frame could not be written to `path`.
+    Export {
+        path: PathBuf,
+        error: image::ImageError,
+    },
+}
+
+impl PistonBackendError {
+    pub(crate) fn texture_upload<E: std::fmt::Debug>(target: &'static str, error: E) -> Self {
+        PistonBackendError::TextureUpload {
+            target,
+            reason: format!("{:?}", error),
+        }
+    }
+}
+
+impl std::fmt::Display for PistonBackendError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PistonBackendError::TextureUpload { target, reason } => {
+                write!(fmt, "failed to upload {} texture: {}", target, reason)
+            }
+            PistonBackendError::InvalidGeometry(e) => write!(fmt, "invalid geometry: {}", e),
+            PistonBackendError::BitmapSize { width, height, len } => write!(
+                fmt,
+                "bitmap of {}x{} pixels needs {} bytes, got {}",
+                width,
+                height,
+                *width as usize * *height as usize * 3,
+                len
+            ),
+            PistonBackendError::Export { path, error } => {
+                write!(fmt, "failed to write {}: {}", path.display(), error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PistonBackendError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PistonBackendError::InvalidGeometry(e) => Some(e),
+            PistonBackendError::Export { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
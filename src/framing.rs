@@ -0,0 +1,192 @@
+/// First two bytes of every frame.
+pub const SYNC_WORD: [u8; 2] = [0xa5, 0x5a];
+
+/// Bytes of a frame besides its samples: the sync word, the sequence number,
+/// the sample count and the checksum.
+const OVERHEAD: usize = 6;
+
+/// CRC-16/CCITT-FALSE: polynomial 0x1021, initial value 0xffff, as computed on
+/// a board by `_crc_xmodem_update` of avr-libc starting from 0xffff.
+pub fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0xffff, |mut crc: u16, &byte| {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+/// Samples of one frame of the binary protocol.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    /// Sequence number, counting frames modulo 256.
+    pub sequence: u8,
+    pub samples: Vec<u16>,
+    /// Number of frames missing, lost or corrupted, between the previous frame
+    /// and this one.
+    pub lost: u8,
+}
+
+/// What a `FrameDecoder` found so far.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameStats {
+    pub frames: u64,
+    /// Frames missing from the sequence numbers.
+    pub lost: u64,
+    /// Frames dropped because their checksum did not match, including sync
+    /// words that turned out to be noise.
+    pub corrupted: u64,
+    /// Bytes dropped while looking for the sync word.
+    pub skipped_bytes: u64,
+}
+
+/// Encoder of the binary protocol, as a board runs it, to test the decoder
+/// without hardware.
+///
+/// A frame is laid out as:
+///
+/// | bytes     | content                                        |
+/// |-----------|------------------------------------------------|
+/// | 2         | sync word `a5 5a`                              |
+/// | 1         | sequence number, incremented modulo 256        |
+/// | 1         | sample count `n`                               |
+/// | 2 × `n`   | samples, little endian `u16`                   |
+/// | 2         | `crc16` of the sequence number, count and samples, little endian |
+///
+/// Ten bit samples in frames of 32 take 2.2 bytes each, against up to six for
+/// the text protocol.
+#[derive(Clone, Debug, Default)]
+pub struct FrameEncoder {
+    sequence: u8,
+}
+
+impl FrameEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The frame of `samples`, with the next sequence number.
+    ///
+    /// Panics if there are more than 255 samples.
+    pub fn encode(&mut self, samples: &[u16]) -> Vec<u8> {
+        assert!(
+            samples.len() <= u8::MAX as usize,
+            "too many samples for a frame"
+        );
+        let mut frame = Vec::with_capacity(OVERHEAD + 2 * samples.len());
+        frame.extend_from_slice(&SYNC_WORD);
+        frame.push(self.sequence);
+        frame.push(samples.len() as u8);
+        for sample in samples {
+            frame.extend_from_slice(&sample.to_le_bytes());
+        }
+        let crc = crc16(&frame[SYNC_WORD.len()..]);
+        frame.extend_from_slice(&crc.to_le_bytes());
+        self.sequence = self.sequence.wrapping_add(1);
+        frame
+    }
+}
+
+/// Decoder of the binary protocol of `FrameEncoder`.
+///
+/// Bytes are pushed as they are read, split anywhere. The decoder looks for the
+/// sync word, drops frames whose checksum does not match, and resynchronises on
+/// the next sync word, so that noise on the line costs only the frames it hits.
+/// Missing frames are reported with the next frame that arrives.
+///
+/// ```ignore
+/// let mut decoder = FrameDecoder::new();
+/// for frame in decoder.push(&buffer[..n]) {
+///     if frame.lost > 0 {
+///         counters.lost_frames(frame.lost as u64);
+///     }
+///     samples.extend(frame.samples);
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct FrameDecoder {
+    buffer: Vec<u8>,
+    next_sequence: Option<u8>,
+    stats: FrameStats,
+}
+
+impl FrameDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decode the frames completed by `bytes`, in order.
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<Frame> {
+        self.buffer.extend_from_slice(bytes);
+        let mut frames = Vec::new();
+        let mut start = 0;
+        loop {
+            let rest = &self.buffer[start..];
+            let sync = match rest.windows(2).position(|pair| pair == SYNC_WORD) {
+                Some(sync) => sync,
+                None => {
+                    // Keep a last byte that may start the sync word
+                    let keep = (rest.last() == Some(&SYNC_WORD[0])) as usize;
+                    self.stats.skipped_bytes += (rest.len() - keep) as u64;
+                    start = self.buffer.len() - keep;
+                    break;
+                }
+            };
+            self.stats.skipped_bytes += sync as u64;
+            start += sync;
+
+            let rest = &self.buffer[start..];
+            if rest.len() < 4 {
+                break;
+            }
+            let len = OVERHEAD + 2 * rest[3] as usize;
+            if rest.len() < len {
+                break;
+            }
+            let crc = u16::from_le_bytes([rest[len - 2], rest[len - 1]]);
+            if crc16(&rest[2..len - 2]) != crc {
+                // The sync word may have been part of the noise: look again
+                // right after it
+                self.stats.corrupted += 1;
+                self.stats.skipped_bytes += 1;
+                start += 1;
+                continue;
+            }
+
+            let sequence = rest[2];
+            let lost = self
+                .next_sequence
+                .map_or(0, |next| sequence.wrapping_sub(next));
+            self.next_sequence = Some(sequence.wrapping_add(1));
+            self.stats.frames += 1;
+            self.stats.lost += lost as u64;
+            frames.push(Frame {
+                sequence,
+                samples: rest[4..len - 2]
+                    .chunks(2)
+                    .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                    .collect(),
+                lost,
+            });
+            start += len;
+        }
+        self.buffer.drain(..start);
+        frames
+    }
+
+    /// Drop the frame in progress and forget the sequence, after a gap in the
+    /// stream.
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.next_sequence = None;
+    }
+
+    pub fn stats(&self) -> FrameStats {
+        self.stats
+    }
+}
@@ -0,0 +1,117 @@
+use piston_window::{Button, Event, Key, PressEvent};
+use plotters_backend::{BackendTextStyle, DrawingBackend, DrawingErrorKind};
+
+use crate::panel::{draw_panel, Corner};
+
+struct Binding<C> {
+    key: Key,
+    command: C,
+    description: String,
+}
+
+/// Keyboard bindings of a viewer, turning key presses into application commands
+/// of type `C`, with a help panel listing them.
+///
+/// ```ignore
+/// let mut keys = KeyBindings::new()
+///     .bind(Key::Space, Command::Freeze, "freeze the frame")
+///     .bind(Key::W, Command::NextWindow, "next window function")
+///     .help(Key::F1, "show this help");
+/// // for every input event
+/// match keys.command(&event) {
+///     Some(Command::Freeze) => frozen = !frozen,
+///     // ...
+/// }
+/// ```
+pub struct KeyBindings<C> {
+    bindings: Vec<Binding<C>>,
+    help: Option<(Key, String)>,
+    help_visible: bool,
+}
+
+impl<C> Default for KeyBindings<C> {
+    fn default() -> Self {
+        Self {
+            bindings: Vec::new(),
+            help: None,
+            help_visible: false,
+        }
+    }
+}
+
+impl<C: Clone> KeyBindings<C> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bind `key` to `command`, replacing an earlier binding of the same key.
+    /// `description` is what the help panel says the key does.
+    pub fn bind<D: Into<String>>(mut self, key: Key, command: C, description: D) -> Self {
+        self.bindings.retain(|binding| binding.key != key);
+        self.bindings.push(Binding {
+            key,
+            command,
+            description: description.into(),
+        });
+        self
+    }
+
+    /// Toggle the help panel with `key`, which is listed in the panel as well.
+    pub fn help<D: Into<String>>(mut self, key: Key, description: D) -> Self {
+        self.help = Some((key, description.into()));
+        self
+    }
+
+    /// The command bound to the key pressed in `event`, if any. Pressing the
+    /// help key toggles the help panel instead.
+    pub fn command(&mut self, event: &Event) -> Option<C> {
+        let key = match event.press_args() {
+            Some(Button::Keyboard(key)) => key,
+            _ => return None,
+        };
+        if self.help.as_ref().map(|help| help.0) == Some(key) {
+            self.help_visible = !self.help_visible;
+            return None;
+        }
+        self.bindings
+            .iter()
+            .find(|binding| binding.key == key)
+            .map(|binding| binding.command.clone())
+    }
+
+    pub fn help_visible(&self) -> bool {
+        self.help_visible
+    }
+
+    pub fn set_help_visible(&mut self, visible: bool) {
+        self.help_visible = visible;
+    }
+
+    /// The name of each bound key with its description, in the order they were
+    /// bound, followed by the help key.
+    pub fn help_lines(&self) -> Vec<(String, &str)> {
+        self.bindings
+            .iter()
+            .map(|binding| (&binding.key, binding.description.as_str()))
+            .chain(
+                self.help
+                    .iter()
+                    .map(|(key, description)| (key, description.as_str())),
+            )
+            .map(|(key, description)| (format!("{:?}", key), description))
+            .collect()
+    }
+
+    /// Draw the help panel in the top left corner of `backend` while it is
+    /// visible, with the text in `style`.
+    pub fn draw_help<DB: DrawingBackend, S: BackendTextStyle>(
+        &self,
+        backend: &mut DB,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<DB::ErrorType>> {
+        if !self.help_visible {
+            return Ok(());
+        }
+        draw_panel(backend, style, Corner::TopLeft, &self.help_lines())
+    }
+}
@@ -0,0 +1,152 @@
+use gfx::format::{DepthStencil, Srgba8};
+use gfx::handle::{DepthStencilView, RenderTargetView};
+use gfx::traits::FactoryExt;
+use gfx::Factory;
+use gfx_device_gl::Resources;
+use piston_window::{
+    Context, G2d, G2dTexture, Graphics, Image, PistonWindow, RenderArgs, Transformed,
+};
+
+use crate::backend::{DrawResult, PistonBackend};
+use crate::cache::RenderCache;
+use crate::error::PistonBackendError;
+
+/// Colour the static part is drawn on, and the window cleared to under it.
+pub(crate) const LAYER_BACKGROUND: [f32; 4] = [1.0; 4];
+
+struct LayerTarget {
+    size: [u32; 2],
+    texture: G2dTexture,
+    color: RenderTargetView<Resources, Srgba8>,
+    stencil: DepthStencilView<Resources, DepthStencil>,
+}
+
+impl LayerTarget {
+    fn new(window: &mut PistonWindow, size: [u32; 2]) -> Result<Self, PistonBackendError> {
+        let (w, h) = (size[0] as u16, size[1] as u16);
+        let (surface, view, color) = window
+            .factory
+            .create_render_target::<Srgba8>(w, h)
+            .map_err(|e| PistonBackendError::texture_upload("layer", e))?;
+        let stencil = window
+            .factory
+            .create_depth_stencil_view_only::<DepthStencil>(w, h)
+            .map_err(|e| PistonBackendError::texture_upload("layer stencil", e))?;
+        let sampler = window.factory.create_sampler_linear();
+        Ok(Self {
+            size,
+            texture: G2dTexture {
+                surface,
+                sampler,
+                view,
+            },
+            color,
+            stencil,
+        })
+    }
+}
+
+/// The static part of a chart (background, mesh, labels, captions) rendered once
+/// to an offscreen texture and composited under the live series every frame.
+///
+/// The layer is re-rendered only when the draw size of the window or the `key`
+/// passed to `try_draw_piston_window_layered` changes; the key is usually the axis
+/// ranges. `T` is whatever the static drawing returns for the live drawing to
+/// reuse, typically the plotters `ChartState` of the chart, so that the live part
+/// can restore the chart coordinates without drawing the mesh again.
+///
+/// The layer is opaque: the static part is drawn on white rather than on
+/// transparent pixels, since translucent content would otherwise be blended
+/// once into the texture and again when the texture is composited, and come
+/// out darker. A static part that fills its background, as with `root.fill`,
+/// covers the white.
+pub struct StaticLayer<K, T> {
+    key: Option<K>,
+    state: Option<T>,
+    target: Option<LayerTarget>,
+}
+
+impl<K, T> Default for StaticLayer<K, T> {
+    fn default() -> Self {
+        Self {
+            key: None,
+            state: None,
+            target: None,
+        }
+    }
+}
+
+impl<K: PartialEq, T> StaticLayer<K, T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Force the static part to be drawn again on the next frame.
+    pub fn invalidate(&mut self) {
+        self.key = None;
+        self.state = None;
+    }
+
+    /// What the last successful static drawing returned.
+    pub fn state(&self) -> Option<&T> {
+        self.state.as_ref()
+    }
+
+    fn is_current(&self, key: &K, size: [u32; 2]) -> bool {
+        self.state.is_some()
+            && self.key.as_ref() == Some(key)
+            && self.target.as_ref().map(|target| target.size) == Some(size)
+    }
+
+    pub(crate) fn update<S>(
+        &mut self,
+        window: &mut PistonWindow,
+        cache: &mut RenderCache,
+        args: &RenderArgs,
+        key: K,
+        draw_static: S,
+    ) -> DrawResult
+    where
+        S: FnOnce(PistonBackend) -> Result<T, Box<dyn std::error::Error>>,
+    {
+        let size = args.draw_size;
+        if self.is_current(&key, size) {
+            return Ok(());
+        }
+        self.invalidate();
+        if self.target.as_ref().map(|target| target.size) != Some(size) {
+            self.target = Some(LayerTarget::new(window, size)?);
+        }
+        let target = self.target.as_ref().unwrap();
+
+        let scale = args.window_size[0] / args.draw_size[0] as f64;
+        let state = window.g2d.draw(
+            &mut window.encoder,
+            &target.color,
+            &target.stencil,
+            args.viewport(),
+            |c, g| {
+                g.clear_color(LAYER_BACKGROUND);
+                g.clear_stencil(0);
+                draw_static(PistonBackend::new((size[0], size[1]), scale, c, g).with_cache(cache))
+            },
+        )?;
+        self.key = Some(key);
+        self.state = Some(state);
+        Ok(())
+    }
+
+    /// Composite the layer over the whole window.
+    pub(crate) fn draw(&self, context: &Context, graphics: &mut G2d) {
+        if let (Some(target), Some(_)) = (self.target.as_ref(), self.state.as_ref()) {
+            let [w, h] = context.get_view_size();
+            // Render targets are stored bottom-up, so the texture is flipped back here.
+            Image::new().rect([0.0, 0.0, w, h]).draw(
+                &target.texture,
+                &context.draw_state,
+                context.transform.trans(0.0, h).flip_v(),
+                graphics,
+            );
+        }
+    }
+}
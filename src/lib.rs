@@ -1,3 +1,46 @@
+mod app;
 mod backend;
+mod batch;
+mod bitmap;
+mod cache;
+mod clip;
+mod connection;
+mod diagnostics;
+mod discovery;
+mod error;
+mod framing;
+mod keys;
+mod layer;
+mod panel;
+mod parser;
+mod pattern;
+mod recording;
+mod screenshot;
+mod serial;
+mod tessellate;
+mod text;
+mod video;
+mod view;
 
-pub use backend::{draw_piston_window, PistonBackend};
+pub use app::{run_piston_app, LoopRates, PistonApp};
+pub use backend::{
+    draw_piston_window, draw_piston_window_cached, try_draw_piston_window,
+    try_draw_piston_window_cached, try_draw_piston_window_layered, DrawResult, PistonBackend,
+};
+pub use cache::RenderCache;
+pub use clip::ClipHandle;
+pub use connection::{Gap, PortAccess, Received, SerialConnection, SourceEvent, SystemPorts};
+pub use diagnostics::{Diagnostics, SourceCounters};
+pub use discovery::{BoardSignature, Discovery, PortProblem, SerialDevice, KNOWN_BOARDS};
+pub use error::PistonBackendError;
+pub use framing::{crc16, Frame, FrameDecoder, FrameEncoder, FrameStats, SYNC_WORD};
+pub use keys::KeyBindings;
+pub use layer::StaticLayer;
+pub use parser::{LineParser, ParseError, ParseErrors};
+pub use pattern::{FillGradient, FillGradientHandle, GradientAxis, LinePattern, LinePatternHandle};
+pub use recording::{DrawCall, RecordedTexture, RecordingGraphics};
+pub use screenshot::{read_frame, save_screenshot};
+pub use serial::{Protocol, SerialSource, SourceError, AUTO_PORT};
+pub use text::{GlyphCache, DEFAULT_ATLAS_SIZE};
+pub use video::{FrameFormat, FrameRecorder};
+pub use view::ChartView;
@@ -0,0 +1,68 @@
+use plotters_backend::{BackendColor, BackendTextStyle, DrawingBackend, DrawingErrorKind};
+
+/// Margin of a panel, and its distance to the corner of the backend, in backend pixels.
+const MARGIN: i32 = 12;
+
+const BACKGROUND: BackendColor = BackendColor {
+    alpha: 0.85,
+    rgb: (255, 255, 255),
+};
+
+const BORDER: BackendColor = BackendColor {
+    alpha: 1.0,
+    rgb: (0, 0, 0),
+};
+
+/// Corner of the backend a panel is drawn in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+}
+
+/// Draw `rows` as a two column table on a white panel in `corner`, with the
+/// text in `style`.
+pub(crate) fn draw_panel<DB, S, L, R>(
+    backend: &mut DB,
+    style: &S,
+    corner: Corner,
+    rows: &[(L, R)],
+) -> Result<(), DrawingErrorKind<DB::ErrorType>>
+where
+    DB: DrawingBackend,
+    S: BackendTextStyle,
+    L: AsRef<str>,
+    R: AsRef<str>,
+{
+    let (mut left_width, mut right_width, mut line_height) = (0, 0, 0);
+    for (left, right) in rows {
+        let (w, h) = backend.estimate_text_size(left.as_ref(), style)?;
+        left_width = left_width.max(w as i32);
+        line_height = line_height.max(h as i32);
+        let (w, h) = backend.estimate_text_size(right.as_ref(), style)?;
+        right_width = right_width.max(w as i32);
+        line_height = line_height.max(h as i32);
+    }
+    let line_height = line_height * 3 / 2;
+
+    let size = (
+        3 * MARGIN + left_width + right_width,
+        2 * MARGIN + line_height * rows.len() as i32,
+    );
+    let origin = match corner {
+        Corner::TopLeft => (MARGIN, MARGIN),
+        Corner::TopRight => (backend.get_size().0 as i32 - MARGIN - size.0, MARGIN),
+        Corner::BottomLeft => (MARGIN, backend.get_size().1 as i32 - MARGIN - size.1),
+    };
+    let corner = (origin.0 + size.0, origin.1 + size.1);
+    backend.draw_rect(origin, corner, &BACKGROUND, true)?;
+    backend.draw_rect(origin, corner, &BORDER, false)?;
+    for (i, (left, right)) in rows.iter().enumerate() {
+        let y = origin.1 + MARGIN + line_height * i as i32;
+        backend.draw_text(left.as_ref(), style, (origin.0 + MARGIN, y))?;
+        let x = origin.0 + 2 * MARGIN + left_width;
+        backend.draw_text(right.as_ref(), style, (x, y))?;
+    }
+    Ok(())
+}
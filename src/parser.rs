@@ -0,0 +1,157 @@
+use std::marker::PhantomData;
+use std::str::{self, FromStr};
+
+/// Longest line kept by default, in bytes, enough for any number a board prints.
+const MAX_LINE: usize = 64;
+
+/// Why a line of a source could not be turned into a sample.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// The line started before the parser was in sync, as when the port is
+    /// opened or the device reconnects in the middle of a line.
+    Partial,
+    /// The line is not text, as when bytes are corrupted right after a reset.
+    InvalidText,
+    /// The line is text, but not a sample.
+    NotANumber,
+    /// The line grew longer than the longest line allowed, and was dropped up
+    /// to the next line boundary.
+    TooLong,
+}
+
+/// Number of lines dropped by a `LineParser`, for each kind of error.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParseErrors {
+    pub partial: u64,
+    pub invalid_text: u64,
+    pub not_a_number: u64,
+    pub too_long: u64,
+}
+
+impl ParseErrors {
+    pub fn total(&self) -> u64 {
+        self.partial + self.invalid_text + self.not_a_number + self.too_long
+    }
+
+    fn count(&mut self, error: ParseError) {
+        match error {
+            ParseError::Partial => self.partial += 1,
+            ParseError::InvalidText => self.invalid_text += 1,
+            ParseError::NotANumber => self.not_a_number += 1,
+            ParseError::TooLong => self.too_long += 1,
+        }
+    }
+}
+
+/// Parser of a stream of samples printed one per line, as by `Serial.println`,
+/// that drops malformed lines instead of failing.
+///
+/// Bytes are pushed as they are read, split anywhere. Each complete line gives a
+/// sample or a `ParseError`; blank lines and the `\r` of `\r\n` line endings are
+/// ignored. The bytes before the first line boundary are dropped as a partial
+/// line, since the source may be opened in the middle of one, and so are the
+/// bytes of a line in progress on `reset`.
+///
+/// ```ignore
+/// let mut parser = LineParser::<isize>::new();
+/// for sample in parser.push(&buffer[..n]) {
+///     match sample {
+///         Ok(value) => samples.push(value),
+///         Err(_) => counters.parse_error(),
+///     }
+/// }
+/// ```
+pub struct LineParser<T> {
+    line: Vec<u8>,
+    max_line: usize,
+    synced: bool,
+    overflowed: bool,
+    errors: ParseErrors,
+    sample: PhantomData<T>,
+}
+
+impl<T> Default for LineParser<T> {
+    fn default() -> Self {
+        Self {
+            line: Vec::new(),
+            max_line: MAX_LINE,
+            synced: false,
+            overflowed: false,
+            errors: ParseErrors::default(),
+            sample: PhantomData,
+        }
+    }
+}
+
+impl<T: FromStr> LineParser<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drop lines longer than `max_line` bytes, without their line ending.
+    pub fn max_line(mut self, max_line: usize) -> Self {
+        self.max_line = max_line;
+        self
+    }
+
+    /// Parse the lines completed by `bytes`, in order.
+    pub fn push(&mut self, bytes: &[u8]) -> Vec<Result<T, ParseError>> {
+        let mut parsed = Vec::new();
+        for &byte in bytes {
+            if byte != b'\n' {
+                if self.line.len() < self.max_line {
+                    self.line.push(byte);
+                } else {
+                    self.overflowed = true;
+                }
+                continue;
+            }
+            if let Some(result) = self.end_line() {
+                if let Err(error) = result {
+                    self.errors.count(error);
+                }
+                parsed.push(result);
+            }
+        }
+        parsed
+    }
+
+    /// Drop the line in progress and wait for the next line boundary, after a
+    /// gap in the stream.
+    pub fn reset(&mut self) {
+        self.line.clear();
+        self.overflowed = false;
+        self.synced = false;
+    }
+
+    /// Lines dropped so far, for each kind of error.
+    pub fn errors(&self) -> ParseErrors {
+        self.errors
+    }
+
+    fn end_line(&mut self) -> Option<Result<T, ParseError>> {
+        let overflowed = std::mem::replace(&mut self.overflowed, false);
+        let synced = std::mem::replace(&mut self.synced, true);
+        let result = if !synced {
+            // Nothing is lost when the stream starts right at a line boundary
+            if self.line.is_empty() && !overflowed {
+                None
+            } else {
+                Some(Err(ParseError::Partial))
+            }
+        } else if overflowed {
+            Some(Err(ParseError::TooLong))
+        } else {
+            match str::from_utf8(&self.line) {
+                Ok(text) if text.chars().any(|c| c.is_control() && !c.is_whitespace()) => {
+                    Some(Err(ParseError::InvalidText))
+                }
+                Ok(text) if text.trim().is_empty() => None,
+                Ok(text) => Some(text.trim().parse().map_err(|_| ParseError::NotANumber)),
+                Err(_) => Some(Err(ParseError::InvalidText)),
+            }
+        };
+        self.line.clear();
+        result
+    }
+}
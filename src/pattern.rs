@@ -0,0 +1,160 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use plotters_backend::BackendColor;
+
+use crate::backend::make_piston_rgba;
+use crate::tessellate;
+
+/// Stroke pattern `PistonBackend` applies to lines, paths and rectangle outlines.
+///
+/// Lengths are in backend pixels and measured along the stroke, so the pattern
+/// runs on through the vertices of a path.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum LinePattern {
+    #[default]
+    Solid,
+    /// Dashes `dash` long separated by gaps `gap` long, with flat ends.
+    Dashed { dash: f32, gap: f32 },
+    /// Round dots as wide as the stroke, `spacing` apart from centre to centre.
+    Dotted { spacing: f32 },
+}
+
+/// Shared handle to the `LinePattern` of a `PistonBackend`.
+///
+/// plotters styles have no dash patterns and the backend is moved into the
+/// drawing area, so the pattern is switched through this handle between two
+/// plotters drawing calls:
+///
+/// ```ignore
+/// let pattern = backend.line_pattern();
+/// let root = backend.into_drawing_area();
+/// // ...
+/// pattern.set(LinePattern::Dashed { dash: 8.0, gap: 4.0 });
+/// chart.draw_series(LineSeries::new(threshold, &BLACK))?;
+/// pattern.set(LinePattern::Solid);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct LinePatternHandle(Rc<Cell<LinePattern>>);
+
+impl LinePatternHandle {
+    pub fn get(&self) -> LinePattern {
+        self.0.get()
+    }
+
+    pub fn set(&self, pattern: LinePattern) {
+        self.0.set(pattern);
+    }
+}
+
+/// Direction along which the colour of a `FillGradient` changes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GradientAxis {
+    /// From left to right, for example along the frequency axis of a spectrum.
+    Horizontal,
+    /// From top to bottom.
+    Vertical,
+}
+
+/// Colour gradient `PistonBackend` fills polygons with, in place of the colour
+/// of their style.
+///
+/// The colour is interpolated linearly between stops placed at backend pixel
+/// coordinates along the axis, and is that of the first or last stop beyond
+/// them. Two stops at the same position make a sharp change, so bands such as
+/// the delta, theta, alpha and beta regions of a spectrum can each get their
+/// own colour.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FillGradient {
+    axis: GradientAxis,
+    stops: Vec<(f32, [f32; 4])>,
+}
+
+impl FillGradient {
+    /// `stops` are pairs of a position along `axis` and the colour at that position.
+    pub fn new<I: IntoIterator<Item = (f32, BackendColor)>>(axis: GradientAxis, stops: I) -> Self {
+        let mut stops: Vec<(f32, [f32; 4])> = stops
+            .into_iter()
+            .map(|(position, color)| (position, make_piston_rgba(&color)))
+            .collect();
+        // Stable, so stops at the same position keep their order
+        stops.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        Self { axis, stops }
+    }
+
+    pub fn horizontal<I: IntoIterator<Item = (f32, BackendColor)>>(stops: I) -> Self {
+        Self::new(GradientAxis::Horizontal, stops)
+    }
+
+    pub fn vertical<I: IntoIterator<Item = (f32, BackendColor)>>(stops: I) -> Self {
+        Self::new(GradientAxis::Vertical, stops)
+    }
+
+    /// Cut a triangle list at the stops and give each vertex its colour. A
+    /// gradient without stops leaves the triangles in `color`, that of the style.
+    pub(crate) fn shade(
+        &self,
+        triangles: &[[f32; 2]],
+        color: [f32; 4],
+    ) -> (Vec<[f32; 2]>, Vec<[f32; 4]>) {
+        if self.stops.is_empty() {
+            return (triangles.to_vec(), vec![color; triangles.len()]);
+        }
+        let axis = match self.axis {
+            GradientAxis::Horizontal => 0,
+            GradientAxis::Vertical => 1,
+        };
+        let cuts: Vec<f32> = self.stops.iter().map(|stop| stop.0).collect();
+        let positions = tessellate::split_triangles(triangles, axis, &cuts);
+        let mut colors = Vec::with_capacity(positions.len());
+        for triangle in positions.chunks_exact(3) {
+            // No triangle crosses a stop, so its centre tells between which stops
+            // it lies: after the last stop at or before the centre. Of two stops
+            // at the same position, the first one is thus used below it and the
+            // second one above it.
+            let centre = triangle.iter().map(|p| p[axis]).sum::<f32>() / 3.0;
+            let last = self.stops.iter().rposition(|stop| stop.0 <= centre);
+            colors.extend(triangle.iter().map(|p| self.color_after(last, p[axis])));
+        }
+        (positions, colors)
+    }
+
+    /// Colour at `at` between the stop `last` and the next one, or that of the
+    /// first stop before all of them when `last` is `None`.
+    fn color_after(&self, last: Option<usize>, at: f32) -> [f32; 4] {
+        let first = match last {
+            Some(first) => first,
+            None => return self.stops[0].1,
+        };
+        let (from, color_from) = self.stops[first];
+        let (to, color_to) = match self.stops.get(first + 1) {
+            Some(&stop) => stop,
+            None => return color_from,
+        };
+        if at <= from || to <= from {
+            return color_from;
+        }
+        let t = ((at - from) / (to - from)).clamp(0.0, 1.0);
+        let mut color = [0.0; 4];
+        for (c, (a, b)) in color.iter_mut().zip(color_from.iter().zip(color_to.iter())) {
+            *c = a + (b - a) * t;
+        }
+        color
+    }
+}
+
+/// Shared handle to the `FillGradient` of a `PistonBackend`, used like
+/// `LinePatternHandle`. Polygons are filled with the colour of their style while
+/// no gradient is set.
+#[derive(Clone, Debug, Default)]
+pub struct FillGradientHandle(Rc<RefCell<Option<FillGradient>>>);
+
+impl FillGradientHandle {
+    pub fn get(&self) -> Option<FillGradient> {
+        self.0.borrow().clone()
+    }
+
+    pub fn set(&self, gradient: Option<FillGradient>) {
+        *self.0.borrow_mut() = gradient;
+    }
+}
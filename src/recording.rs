@@ -0,0 +1,442 @@
+use image::{Rgba, RgbaImage};
+use piston_window::context::Context;
+use piston_window::{DrawState, Graphics, ImageSize};
+use texture::{CreateTexture, Format, TextureOp, TextureSettings, UpdateTexture};
+
+use crate::backend::PistonBackend;
+
+/// Recorded positions are snapped to this fraction of a pixel, so that a point
+/// drawn at integer backend coordinates is recorded at exactly those coordinates.
+const SUBPIXEL_STEPS: f64 = 256.0;
+
+/// One submission made to `RecordingGraphics`, with positions in window pixels.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DrawCall {
+    ClearColor([f32; 4]),
+    ClearStencil(u8),
+    /// Coloured triangles, three vertices per triangle.
+    Triangles {
+        positions: Vec<[f32; 2]>,
+        colors: Vec<[f32; 4]>,
+    },
+    /// Triangles textured with a texture of `texture_size`, tinted per vertex.
+    Textured {
+        positions: Vec<[f32; 2]>,
+        uvs: Vec<[f32; 2]>,
+        colors: Vec<[f32; 4]>,
+        texture_size: (u32, u32),
+    },
+}
+
+/// Texture of `RecordingGraphics`, an RGBA image kept in memory.
+///
+/// Created and updated with the unit texture factory, so a `RenderCache` for
+/// recording is `RenderCache::<RecordedTexture, ()>::with_texture_context(())`.
+pub struct RecordedTexture {
+    image: RgbaImage,
+}
+
+impl RecordedTexture {
+    pub fn image(&self) -> &RgbaImage {
+        &self.image
+    }
+}
+
+impl ImageSize for RecordedTexture {
+    fn get_size(&self) -> (u32, u32) {
+        self.image.dimensions()
+    }
+}
+
+impl TextureOp<()> for RecordedTexture {
+    type Error = String;
+}
+
+impl CreateTexture<()> for RecordedTexture {
+    fn create<S: Into<[u32; 2]>>(
+        _factory: &mut (),
+        _format: Format,
+        memory: &[u8],
+        size: S,
+        _settings: &TextureSettings,
+    ) -> Result<Self, Self::Error> {
+        let [width, height] = size.into();
+        let len = width as usize * height as usize * 4;
+        if memory.len() < len {
+            return Err(format!(
+                "{} bytes for a {}x{} texture",
+                memory.len(),
+                width,
+                height
+            ));
+        }
+        let image = RgbaImage::from_raw(width, height, memory[..len].to_vec())
+            .expect("buffer matches texture size");
+        Ok(Self { image })
+    }
+}
+
+impl UpdateTexture<()> for RecordedTexture {
+    fn update<O: Into<[u32; 2]>, S: Into<[u32; 2]>>(
+        &mut self,
+        _factory: &mut (),
+        _format: Format,
+        memory: &[u8],
+        offset: O,
+        size: S,
+    ) -> Result<(), Self::Error> {
+        let ([x0, y0], [width, height]) = (offset.into(), size.into());
+        let (tw, th) = self.image.dimensions();
+        if x0 + width > tw || y0 + height > th {
+            return Err(format!(
+                "{}x{} update at ({}, {}) outside a {}x{} texture",
+                width, height, x0, y0, tw, th
+            ));
+        }
+        if memory.len() < width as usize * height as usize * 4 {
+            return Err(format!(
+                "{} bytes for a {}x{} update",
+                memory.len(),
+                width,
+                height
+            ));
+        }
+        for (i, rgba) in memory.chunks(4).take((width * height) as usize).enumerate() {
+            let (x, y) = (i as u32 % width, i as u32 / width);
+            self.image
+                .put_pixel(x0 + x, y0 + y, Rgba([rgba[0], rgba[1], rgba[2], rgba[3]]));
+        }
+        Ok(())
+    }
+}
+
+/// Software implementation of the piston `Graphics` trait for tests and headless
+/// rendering.
+///
+/// Every submission is recorded as a `DrawCall`, in the order `PistonBackend`
+/// makes it, and rasterized into an RGBA image. The rasterizer samples pixel
+/// centres without anti-aliasing and blends with straight alpha, so the same
+/// drawing always gives the same image, which can then be compared with a
+/// golden file.
+pub struct RecordingGraphics {
+    calls: Vec<DrawCall>,
+    image: RgbaImage,
+}
+
+impl RecordingGraphics {
+    /// A transparent black canvas of `width` by `height` pixels.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            calls: Vec::new(),
+            image: RgbaImage::new(width, height),
+        }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.image.dimensions()
+    }
+
+    /// Context mapping window pixels to the whole canvas.
+    pub fn context(&self) -> Context {
+        let (width, height) = self.size();
+        Context::new_abs(width as f64, height as f64)
+    }
+
+    /// Backend drawing on the whole canvas, one backend pixel per canvas pixel.
+    pub fn backend(&mut self) -> PistonBackend<'static, '_, Self, ()> {
+        let (size, context) = (self.size(), self.context());
+        PistonBackend::new(size, 1.0, context, self)
+    }
+
+    pub fn calls(&self) -> &[DrawCall] {
+        &self.calls
+    }
+
+    /// Return the calls recorded so far and start a new recording. The image is kept.
+    pub fn take_calls(&mut self) -> Vec<DrawCall> {
+        std::mem::take(&mut self.calls)
+    }
+
+    pub fn image(&self) -> &RgbaImage {
+        &self.image
+    }
+
+    pub fn into_image(self) -> RgbaImage {
+        self.image
+    }
+
+    /// Convert a position from normalized device coordinates to window pixels.
+    fn to_pixels(&self, [x, y]: [f32; 2]) -> [f32; 2] {
+        let (width, height) = self.size();
+        let snap = |v: f64| ((v * SUBPIXEL_STEPS).round() / SUBPIXEL_STEPS) as f32;
+        [
+            snap((x as f64 + 1.0) * width as f64 / 2.0),
+            snap((1.0 - y as f64) * height as f64 / 2.0),
+        ]
+    }
+
+    fn rasterize(
+        &mut self,
+        draw_state: &DrawState,
+        positions: &[[f32; 2]],
+        colors: &[[f32; 4]],
+        texture: Option<(&RgbaImage, &[[f32; 2]])>,
+    ) {
+        let (width, height) = self.size();
+        let clip = match draw_state.scissor {
+            Some([x, y, w, h]) => [x, y, (x + w).min(width), (y + h).min(height)],
+            None => [0, 0, width, height],
+        };
+        let blend = draw_state.blend.is_some();
+        for (i, triangle) in positions.chunks_exact(3).enumerate() {
+            let vertex = |j: usize| {
+                let [x, y] = triangle[j];
+                [x as f64, y as f64]
+            };
+            let (first, image) = (i * 3, &mut self.image);
+            fill_triangle(
+                clip,
+                [vertex(0), vertex(1), vertex(2)],
+                |weights| {
+                    // Offsets from the first colour, so that a flat triangle
+                    // keeps its colour exactly
+                    let mut color = colors[first];
+                    for (k, weight) in weights.iter().enumerate().skip(1) {
+                        for (c, (v, v0)) in color
+                            .iter_mut()
+                            .zip(colors[first + k].iter().zip(colors[first].iter()))
+                        {
+                            *c += (*v - *v0) * *weight as f32;
+                        }
+                    }
+                    if let Some((texture, uvs)) = texture {
+                        let (mut u, mut v) = (0.0, 0.0);
+                        for (k, weight) in weights.iter().enumerate() {
+                            u += uvs[first + k][0] as f64 * weight;
+                            v += uvs[first + k][1] as f64 * weight;
+                        }
+                        let texel = sample(texture, u, v);
+                        for (c, t) in color.iter_mut().zip(texel.iter()) {
+                            *c *= *t;
+                        }
+                    }
+                    color
+                },
+                |x, y, color| {
+                    let pixel = image.get_pixel_mut(x, y);
+                    pixel.0 = if blend {
+                        blend_over(pixel.0, color)
+                    } else {
+                        to_rgba8(color)
+                    };
+                },
+            );
+        }
+    }
+
+    fn record_triangles<F>(&mut self, draw_state: &DrawState, uniform: Option<[f32; 4]>, mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 4]])),
+    {
+        let (mut positions, mut colors) = (Vec::new(), Vec::new());
+        f(&mut |chunk, chunk_colors| {
+            positions.extend(chunk.iter().map(|&p| self.to_pixels(p)));
+            match uniform {
+                Some(color) => colors.extend(chunk.iter().map(|_| color)),
+                None => colors.extend_from_slice(chunk_colors),
+            }
+        });
+        self.rasterize(draw_state, &positions, &colors, None);
+        self.calls.push(DrawCall::Triangles { positions, colors });
+    }
+
+    fn record_textured<F>(
+        &mut self,
+        draw_state: &DrawState,
+        texture: &RecordedTexture,
+        uniform: Option<[f32; 4]>,
+        mut f: F,
+    ) where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]], &[[f32; 4]])),
+    {
+        let (mut positions, mut uvs, mut colors) = (Vec::new(), Vec::new(), Vec::new());
+        f(&mut |chunk, chunk_uvs, chunk_colors| {
+            positions.extend(chunk.iter().map(|&p| self.to_pixels(p)));
+            uvs.extend_from_slice(chunk_uvs);
+            match uniform {
+                Some(color) => colors.extend(chunk.iter().map(|_| color)),
+                None => colors.extend_from_slice(chunk_colors),
+            }
+        });
+        self.rasterize(
+            draw_state,
+            &positions,
+            &colors,
+            Some((&texture.image, &uvs)),
+        );
+        self.calls.push(DrawCall::Textured {
+            positions,
+            uvs,
+            colors,
+            texture_size: texture.get_size(),
+        });
+    }
+}
+
+impl Graphics for RecordingGraphics {
+    type Texture = RecordedTexture;
+
+    fn clear_color(&mut self, color: [f32; 4]) {
+        let rgba = to_rgba8(color);
+        for pixel in self.image.pixels_mut() {
+            pixel.0 = rgba;
+        }
+        self.calls.push(DrawCall::ClearColor(color));
+    }
+
+    fn clear_stencil(&mut self, value: u8) {
+        self.calls.push(DrawCall::ClearStencil(value));
+    }
+
+    fn tri_list<F>(&mut self, draw_state: &DrawState, color: &[f32; 4], mut f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]])),
+    {
+        self.record_triangles(draw_state, Some(*color), |g| {
+            f(&mut |positions| g(positions, &[]))
+        });
+    }
+
+    fn tri_list_c<F>(&mut self, draw_state: &DrawState, f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 4]])),
+    {
+        self.record_triangles(draw_state, None, f);
+    }
+
+    fn tri_list_uv<F>(
+        &mut self,
+        draw_state: &DrawState,
+        color: &[f32; 4],
+        texture: &RecordedTexture,
+        mut f: F,
+    ) where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]])),
+    {
+        self.record_textured(draw_state, texture, Some(*color), |g| {
+            f(&mut |positions, uvs| g(positions, uvs, &[]))
+        });
+    }
+
+    fn tri_list_uv_c<F>(&mut self, draw_state: &DrawState, texture: &RecordedTexture, f: F)
+    where
+        F: FnMut(&mut dyn FnMut(&[[f32; 2]], &[[f32; 2]], &[[f32; 4]])),
+    {
+        self.record_textured(draw_state, texture, None, f);
+    }
+}
+
+fn edge(a: [f64; 2], b: [f64; 2], c: [f64; 2]) -> f64 {
+    (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+}
+
+/// Whether pixel centres lying exactly on the edge from `a` to `b` belong to the
+/// triangle, so that triangles sharing an edge never both cover a pixel.
+fn is_top_left(a: [f64; 2], b: [f64; 2]) -> bool {
+    (a[1] == b[1] && b[0] > a[0]) || b[1] < a[1]
+}
+
+/// Call `plot` for every pixel inside `clip` whose centre is covered by the
+/// triangle, with the colour `shade` gives for the barycentric weights of that
+/// centre.
+fn fill_triangle(
+    clip: [u32; 4],
+    mut v: [[f64; 2]; 3],
+    mut shade: impl FnMut([f64; 3]) -> [f32; 4],
+    mut plot: impl FnMut(u32, u32, [f32; 4]),
+) {
+    let mut area = edge(v[0], v[1], v[2]);
+    if area == 0.0 || !area.is_finite() {
+        return;
+    }
+    // Weights are computed for the vertices in their original order.
+    let mut order = [0, 1, 2];
+    if area < 0.0 {
+        v.swap(1, 2);
+        order.swap(1, 2);
+        area = -area;
+    }
+
+    let bound = |axis: usize, lo: u32, hi: u32| {
+        let min = v.iter().map(|p| p[axis]).fold(f64::INFINITY, f64::min);
+        let max = v.iter().map(|p| p[axis]).fold(f64::NEG_INFINITY, f64::max);
+        let first = (min - 0.5).ceil().max(lo as f64) as u32;
+        let last = (max - 0.5).floor().min(hi as f64 - 1.0);
+        (first, last)
+    };
+    let (x0, x1) = bound(0, clip[0], clip[2]);
+    let (y0, y1) = bound(1, clip[1], clip[3]);
+    if x1 < 0.0 || y1 < 0.0 {
+        return;
+    }
+
+    let edges = [(v[1], v[2]), (v[2], v[0]), (v[0], v[1])];
+    for y in y0..=y1 as u32 {
+        for x in x0..=x1 as u32 {
+            let centre = [x as f64 + 0.5, y as f64 + 0.5];
+            let mut weights = [0.0; 3];
+            let inside = edges.iter().enumerate().all(|(i, &(a, b))| {
+                let w = edge(a, b, centre);
+                weights[order[i]] = w / area;
+                w > 0.0 || (w == 0.0 && is_top_left(a, b))
+            });
+            if inside {
+                plot(x, y, shade(weights));
+            }
+        }
+    }
+}
+
+/// Nearest texel at normalized texture coordinates, as straight RGBA in 0..1.
+fn sample(texture: &RgbaImage, u: f64, v: f64) -> [f32; 4] {
+    let (width, height) = texture.dimensions();
+    if width == 0 || height == 0 {
+        return [0.0; 4];
+    }
+    let x = ((u * width as f64).floor().max(0.0) as u32).min(width - 1);
+    let y = ((v * height as f64).floor().max(0.0) as u32).min(height - 1);
+    let texel = texture.get_pixel(x, y).0;
+    [
+        texel[0] as f32 / 255.0,
+        texel[1] as f32 / 255.0,
+        texel[2] as f32 / 255.0,
+        texel[3] as f32 / 255.0,
+    ]
+}
+
+fn to_rgba8(color: [f32; 4]) -> [u8; 4] {
+    let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+    [
+        channel(color[0]),
+        channel(color[1]),
+        channel(color[2]),
+        channel(color[3]),
+    ]
+}
+
+/// Straight alpha "source over" blending of `color` onto `dst`.
+fn blend_over(dst: [u8; 4], color: [f32; 4]) -> [u8; 4] {
+    let src_a = color[3].clamp(0.0, 1.0);
+    let dst_a = dst[3] as f32 / 255.0;
+    let out_a = src_a + dst_a * (1.0 - src_a);
+    if out_a == 0.0 {
+        return [0; 4];
+    }
+    let mut out = [0.0; 4];
+    for i in 0..3 {
+        let dst_c = dst[i] as f32 / 255.0;
+        out[i] = (color[i] * src_a + dst_c * dst_a * (1.0 - src_a)) / out_a;
+    }
+    out[3] = out_a;
+    to_rgba8(out)
+}
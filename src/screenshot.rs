@@ -0,0 +1,103 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use gfx_gl as gl;
+use image::RgbaImage;
+use piston_window::{PistonWindow, Window};
+
+use crate::error::PistonBackendError;
+
+/// Read the frame last drawn to `window`, at its draw size.
+///
+/// The frame is read from the back buffer, so call this right after a
+/// `draw_piston_window` function returned a render event, before the next call
+/// swaps the buffers. The alpha channel is made opaque, as the window shows it.
+pub fn read_frame(window: &mut PistonWindow) -> RgbaImage {
+    let size = window.draw_size();
+    let (width, height) = (size.width as u32, size.height as u32);
+    let mut pixels = vec![0u8; width as usize * height as usize * 4];
+    unsafe {
+        window.device.with_gl(|gl| {
+            let (mut read_framebuffer, mut pack_alignment) = (0, 0);
+            gl.GetIntegerv(gl::READ_FRAMEBUFFER_BINDING, &mut read_framebuffer);
+            gl.GetIntegerv(gl::PACK_ALIGNMENT, &mut pack_alignment);
+            gl.BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+            gl.ReadBuffer(gl::BACK);
+            gl.PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl.ReadPixels(
+                0,
+                0,
+                width as i32,
+                height as i32,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_mut_ptr() as *mut _,
+            );
+            // gfx tracks the GL state itself, so leave it as it was
+            gl.PixelStorei(gl::PACK_ALIGNMENT, pack_alignment);
+            gl.BindFramebuffer(gl::READ_FRAMEBUFFER, read_framebuffer as u32);
+        });
+    }
+
+    // OpenGL rows go from the bottom of the window to the top
+    let row = width as usize * 4;
+    let mut image = Vec::with_capacity(pixels.len());
+    for line in pixels.chunks(row.max(1)).rev() {
+        image.extend_from_slice(line);
+    }
+    for alpha in image.iter_mut().skip(3).step_by(4) {
+        *alpha = 255;
+    }
+    RgbaImage::from_raw(width, height, image).expect("buffer matches draw size")
+}
+
+/// Save the frame last drawn to `window` as a PNG file in `dir`, named after the
+/// current UTC time, and return the path of the file.
+///
+/// See `read_frame` for when to call it.
+pub fn save_screenshot<P: AsRef<Path>>(
+    window: &mut PistonWindow,
+    dir: P,
+) -> Result<PathBuf, PistonBackendError> {
+    let path = dir
+        .as_ref()
+        .join(format!("screenshot-{}.png", timestamp(SystemTime::now())));
+    read_frame(window)
+        .save(&path)
+        .map_err(|error| PistonBackendError::Export {
+            path: path.clone(),
+            error,
+        })?;
+    Ok(path)
+}
+
+/// `time` as `YYYYMMDD-HHMMSS-mmm` in UTC.
+pub(crate) fn timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let (days, seconds) = (
+        since_epoch.as_secs() / 86_400,
+        since_epoch.as_secs() % 86_400,
+    );
+
+    // Civil date from a day count, after Howard Hinnant's `civil_from_days`
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}-{:03}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        since_epoch.subsec_millis()
+    )
+}
@@ -0,0 +1,322 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serialport::{DataBits, FlowControl, Parity, SerialPort, StopBits};
+
+use crate::discovery::{Discovery, PortProblem};
+
+/// Port name that stands for the one known board connected, see `Discovery`.
+pub const AUTO_PORT: &str = "auto";
+
+/// How a board writes its samples to the serial port.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Protocol {
+    /// One sample per line, as by `Serial.println`, read with `LineParser`.
+    Text,
+    /// Frames with sequence numbers and checksums, read with `FrameDecoder`.
+    Binary,
+}
+
+/// Settings of the serial port a board streams its samples through.
+///
+/// The settings start at the defaults of the boards in this repository, 115200
+/// baud 8N1 without flow control on `/dev/ttyUSB0` with a 10 ms read timeout,
+/// sending text, and can be changed with the builder methods, a config file or
+/// command line flags:
+///
+/// ```ignore
+/// // --config lab.conf --port /dev/ttyACM0 --baud 230400
+/// let source = SerialSource::from_args(std::env::args().skip(1))?;
+/// let port = source.open()?;
+/// ```
+///
+/// Config files hold one `setting = value` per line, with `#` starting a
+/// comment. Settings and flags share the same names: `port`, `baud`,
+/// `data-bits` (5 to 8), `parity` (`none`, `odd`, `even`), `stop-bits` (1 or
+/// 2), `flow-control` (`none`, `software`, `hardware`), `timeout-ms` and
+/// `protocol` (`text`, `binary`). A `port` of `auto` opens the one board of
+/// `KNOWN_BOARDS` that is connected.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SerialSource {
+    port: String,
+    baud_rate: u32,
+    data_bits: DataBits,
+    parity: Parity,
+    stop_bits: StopBits,
+    flow_control: FlowControl,
+    timeout: Duration,
+    protocol: Protocol,
+}
+
+impl Default for SerialSource {
+    fn default() -> Self {
+        Self::new("/dev/ttyUSB0")
+    }
+}
+
+impl SerialSource {
+    pub fn new<P: Into<String>>(port: P) -> Self {
+        Self {
+            port: port.into(),
+            baud_rate: 115_200,
+            data_bits: DataBits::Eight,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+            flow_control: FlowControl::None,
+            timeout: Duration::from_millis(10),
+            protocol: Protocol::Text,
+        }
+    }
+
+    /// The default settings, changed by the command line flags in `args`, after
+    /// those of the config file named by a `--config` flag. Flags are given as
+    /// `--baud 230400` or `--baud=230400`.
+    pub fn from_args<I, S>(args: I) -> Result<Self, SourceError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let args: Vec<String> = args.into_iter().map(|a| a.as_ref().to_string()).collect();
+        let mut flags = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let flag = arg
+                .strip_prefix("--")
+                .ok_or_else(|| SourceError::invalid_setting("argument", arg))?;
+            let (name, value) = match flag.split_once('=') {
+                Some((name, value)) => (name, value.to_string()),
+                None => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| SourceError::invalid_setting(flag, ""))?;
+                    (flag, value.clone())
+                }
+            };
+            flags.push((name, value));
+        }
+
+        let mut source = Self::default();
+        for (_, path) in flags.iter().filter(|(name, _)| *name == "config") {
+            source = source.with_config_file(path)?;
+        }
+        for (name, value) in flags.iter().filter(|(name, _)| *name != "config") {
+            source = source.with_setting(name, value)?;
+        }
+        Ok(source)
+    }
+
+    /// The default settings, changed by the config file at `path`.
+    pub fn from_config_file<P: AsRef<Path>>(path: P) -> Result<Self, SourceError> {
+        Self::default().with_config_file(path)
+    }
+
+    /// These settings, changed by the config file at `path`.
+    pub fn with_config_file<P: AsRef<Path>>(self, path: P) -> Result<Self, SourceError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|error| SourceError::Config {
+            path: path.to_path_buf(),
+            error,
+        })?;
+        self.with_config(&text)
+    }
+
+    /// These settings, changed by the lines of a config file.
+    pub fn with_config(mut self, text: &str) -> Result<Self, SourceError> {
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (name, value) = line
+                .split_once('=')
+                .ok_or_else(|| SourceError::invalid_setting(line, ""))?;
+            self = self.with_setting(name.trim(), value.trim())?;
+        }
+        Ok(self)
+    }
+
+    /// Change the setting called `name`, as in config files, to `value`.
+    pub fn with_setting(self, name: &str, value: &str) -> Result<Self, SourceError> {
+        let invalid = || SourceError::invalid_setting(name, value);
+        Ok(match name.replace('_', "-").as_str() {
+            "port" => self.port(value),
+            "baud" => self.baud_rate(value.parse().map_err(|_| invalid())?),
+            "data-bits" => self.data_bits(match value {
+                "5" => DataBits::Five,
+                "6" => DataBits::Six,
+                "7" => DataBits::Seven,
+                "8" => DataBits::Eight,
+                _ => return Err(invalid()),
+            }),
+            "parity" => self.parity(match value.to_ascii_lowercase().as_str() {
+                "none" => Parity::None,
+                "odd" => Parity::Odd,
+                "even" => Parity::Even,
+                _ => return Err(invalid()),
+            }),
+            "stop-bits" => self.stop_bits(match value {
+                "1" => StopBits::One,
+                "2" => StopBits::Two,
+                _ => return Err(invalid()),
+            }),
+            "flow-control" => self.flow_control(match value.to_ascii_lowercase().as_str() {
+                "none" => FlowControl::None,
+                "software" => FlowControl::Software,
+                "hardware" => FlowControl::Hardware,
+                _ => return Err(invalid()),
+            }),
+            "timeout-ms" => {
+                self.timeout(Duration::from_millis(value.parse().map_err(|_| invalid())?))
+            }
+            "protocol" => self.protocol(match value.to_ascii_lowercase().as_str() {
+                "text" => Protocol::Text,
+                "binary" => Protocol::Binary,
+                _ => return Err(invalid()),
+            }),
+            _ => return Err(invalid()),
+        })
+    }
+
+    pub fn port<P: Into<String>>(mut self, port: P) -> Self {
+        self.port = port.into();
+        self
+    }
+
+    pub fn baud_rate(mut self, baud_rate: u32) -> Self {
+        self.baud_rate = baud_rate;
+        self
+    }
+
+    pub fn data_bits(mut self, data_bits: DataBits) -> Self {
+        self.data_bits = data_bits;
+        self
+    }
+
+    pub fn parity(mut self, parity: Parity) -> Self {
+        self.parity = parity;
+        self
+    }
+
+    pub fn stop_bits(mut self, stop_bits: StopBits) -> Self {
+        self.stop_bits = stop_bits;
+        self
+    }
+
+    pub fn flow_control(mut self, flow_control: FlowControl) -> Self {
+        self.flow_control = flow_control;
+        self
+    }
+
+    /// How long a read waits for data before it fails with `TimedOut`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// How the board writes its samples, which is not a setting of the port
+    /// itself but of what is read from it.
+    pub fn protocol(mut self, protocol: Protocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    pub fn port_name(&self) -> &str {
+        &self.port
+    }
+
+    pub fn wire_protocol(&self) -> Protocol {
+        self.protocol
+    }
+
+    /// The port to open: the port setting, or the port of the one known board
+    /// connected when the setting is `auto`.
+    pub fn resolve_port(&self) -> Result<String, SourceError> {
+        if self.port != AUTO_PORT {
+            return Ok(self.port.clone());
+        }
+        Discovery::scan()?.port_name()
+    }
+
+    /// Open the port with these settings.
+    pub fn open(&self) -> Result<Box<dyn SerialPort>, SourceError> {
+        let port = self.resolve_port()?;
+        serialport::new(port.as_str(), self.baud_rate)
+            .data_bits(self.data_bits)
+            .parity(self.parity)
+            .stop_bits(self.stop_bits)
+            .flow_control(self.flow_control)
+            .timeout(self.timeout)
+            .open()
+            .map_err(|error| SourceError::Serial { port, error })
+    }
+}
+
+/// Errors reported while configuring, finding and opening a serial source.
+#[derive(Debug)]
+pub enum SourceError {
+    /// A setting, from a config file or command line flag, is unknown or has
+    /// an invalid or missing value.
+    InvalidSetting { name: String, value: String },
+    /// The config file at `path` could not be read.
+    Config { path: PathBuf, error: io::Error },
+    /// The serial port `port` could not be opened.
+    Serial {
+        port: String,
+        error: serialport::Error,
+    },
+    /// The serial ports could not be listed.
+    PortList(serialport::Error),
+    /// No known board is connected.
+    NoBoard,
+    /// Known boards are connected on each of these ports, and one has to be
+    /// chosen.
+    SeveralBoards(Vec<String>),
+}
+
+impl SourceError {
+    fn invalid_setting(name: &str, value: &str) -> Self {
+        SourceError::InvalidSetting {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for SourceError {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SourceError::InvalidSetting { name, value } if value.is_empty() => {
+                write!(fmt, "missing value for {}", name)
+            }
+            SourceError::InvalidSetting { name, value } => {
+                write!(fmt, "invalid value {:?} for {}", value, name)
+            }
+            SourceError::Config { path, error } => {
+                write!(fmt, "failed to read {}: {}", path.display(), error)
+            }
+            SourceError::Serial { port, error } => {
+                write!(fmt, "failed to open {}: {}", port, PortProblem::of(error))
+            }
+            SourceError::PortList(e) => write!(fmt, "failed to list serial ports: {}", e),
+            SourceError::NoBoard => write!(fmt, "no known board is connected"),
+            SourceError::SeveralBoards(ports) => write!(
+                fmt,
+                "several boards are connected, on {}; choose one of the ports",
+                ports.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SourceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SourceError::Config { error, .. } => Some(error),
+            SourceError::Serial { error, .. } => Some(error),
+            SourceError::PortList(e) => Some(e),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use lyon_tessellation::math::Point;
+use lyon_tessellation::path::Polygon;
+use lyon_tessellation::{
+    BuffersBuilder, FillOptions, FillTessellator, FillVertex, LineJoin, StrokeOptions,
+    StrokeTessellator, StrokeVertex, TessellationError, VertexBuffers,
+};
+
+type Buffers = VertexBuffers<[f32; 2], u32>;
+
+/// Expand an indexed mesh into a plain triangle list, three vertices per triangle.
+fn triangles(buffers: Buffers) -> Vec<[f32; 2]> {
+    buffers
+        .indices
+        .iter()
+        .map(|&i| buffers.vertices[i as usize])
+        .collect()
+}
+
+/// Tessellate an open polyline of the given width, with round joins between segments.
+pub(crate) fn stroke_polyline(
+    points: &[Point],
+    width: f32,
+) -> Result<Vec<[f32; 2]>, TessellationError> {
+    let mut buffers = Buffers::new();
+    StrokeTessellator::new().tessellate_polygon(
+        Polygon {
+            points,
+            closed: false,
+        },
+        &StrokeOptions::default()
+            .with_line_width(width)
+            .with_line_join(LineJoin::Round),
+        &mut BuffersBuilder::new(&mut buffers, |v: StrokeVertex| v.position().to_array()),
+    )?;
+    Ok(triangles(buffers))
+}
+
+/// Tessellate the interior of a closed, possibly concave or self-intersecting polygon.
+pub(crate) fn fill_polygon(points: &[Point]) -> Result<Vec<[f32; 2]>, TessellationError> {
+    let mut buffers = Buffers::new();
+    FillTessellator::new().tessellate_polygon(
+        Polygon {
+            points,
+            closed: true,
+        },
+        &FillOptions::default(),
+        &mut BuffersBuilder::new(&mut buffers, |v: FillVertex| v.position().to_array()),
+    )?;
+    Ok(triangles(buffers))
+}
+
+/// Cut a polyline into the dashes of a `dash` on, `gap` off pattern. The pattern
+/// runs continuously through the vertices of the polyline.
+pub(crate) fn dash_polyline(points: &[Point], dash: f32, gap: f32) -> Vec<Vec<Point>> {
+    if dash <= 0.0 || gap <= 0.0 {
+        return vec![points.to_vec()];
+    }
+    let push = |dash: &mut Vec<Point>, p: Point| {
+        if dash.last() != Some(&p) {
+            dash.push(p);
+        }
+    };
+    let mut dashes = Vec::new();
+    let mut current: Vec<Point> = points.iter().take(1).cloned().collect();
+    let (mut on, mut left) = (true, dash);
+    for pair in points.windows(2) {
+        let (mut from, to) = (pair[0], pair[1]);
+        let mut len = (to - from).length();
+        while len > left {
+            let split = from.lerp(to, left / len);
+            push(&mut current, split);
+            if on && current.len() > 1 {
+                dashes.push(std::mem::take(&mut current));
+            } else if on {
+                current.clear();
+            }
+            len -= left;
+            from = split;
+            on = !on;
+            left = if on { dash } else { gap };
+        }
+        left -= len;
+        if on {
+            push(&mut current, to);
+        }
+    }
+    if on && current.len() > 1 {
+        dashes.push(current);
+    }
+    dashes
+}
+
+/// Centres of dots placed every `spacing` along a polyline, starting at its first point.
+pub(crate) fn dot_centers(points: &[Point], spacing: f32) -> Vec<Point> {
+    if points.len() == 1 || spacing <= 0.0 {
+        return points.iter().take(1).cloned().collect();
+    }
+    let mut dots = Vec::new();
+    let mut offset = 0.0;
+    for pair in points.windows(2) {
+        let (from, to) = (pair[0], pair[1]);
+        let len = (to - from).length();
+        while offset <= len {
+            let t = if len > 0.0 { offset / len } else { 0.0 };
+            dots.push(from.lerp(to, t));
+            offset += spacing;
+        }
+        offset -= len;
+    }
+    dots
+}
+
+/// Cut a triangle list along the lines where coordinate `axis` equals one of
+/// `cuts`, so that none of the resulting triangles crosses one of those lines.
+pub(crate) fn split_triangles(triangles: &[[f32; 2]], axis: usize, cuts: &[f32]) -> Vec<[f32; 2]> {
+    let mut split = Vec::with_capacity(triangles.len());
+    for triangle in triangles.chunks_exact(3) {
+        let min = triangle
+            .iter()
+            .map(|p| p[axis])
+            .fold(f32::INFINITY, f32::min);
+        let max = triangle
+            .iter()
+            .map(|p| p[axis])
+            .fold(f32::NEG_INFINITY, f32::max);
+        let mut pieces = vec![triangle.to_vec()];
+        for &cut in cuts.iter().filter(|&&cut| cut > min && cut < max) {
+            let mut next = Vec::with_capacity(pieces.len() + 1);
+            for piece in pieces {
+                let (below, above) = split_polygon(&piece, axis, cut);
+                next.push(below);
+                next.push(above);
+            }
+            next.retain(|piece| piece.len() >= 3);
+            pieces = next;
+        }
+        // Every piece is convex, so a fan triangulates it
+        for piece in pieces {
+            for i in 1..piece.len() - 1 {
+                split.extend_from_slice(&[piece[0], piece[i], piece[i + 1]]);
+            }
+        }
+    }
+    split
+}
+
+/// Split a convex polygon in the parts below and above `cut` along `axis`.
+fn split_polygon(points: &[[f32; 2]], axis: usize, cut: f32) -> (Vec<[f32; 2]>, Vec<[f32; 2]>) {
+    let (mut below, mut above) = (Vec::new(), Vec::new());
+    for (i, &p) in points.iter().enumerate() {
+        let q = points[(i + 1) % points.len()];
+        if p[axis] <= cut {
+            below.push(p);
+        }
+        if p[axis] >= cut {
+            above.push(p);
+        }
+        if (p[axis] < cut && q[axis] > cut) || (p[axis] > cut && q[axis] < cut) {
+            let t = (cut - p[axis]) / (q[axis] - p[axis]);
+            let mut crossing = [p[0] + (q[0] - p[0]) * t, p[1] + (q[1] - p[1]) * t];
+            crossing[axis] = cut;
+            below.push(crossing);
+            above.push(crossing);
+        }
+    }
+    (below, above)
+}
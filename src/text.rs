@@ -0,0 +1,329 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+
+use image::{Rgba, RgbaImage};
+use piston_window::types::{Rectangle, SourceRectangle};
+use piston_window::G2dTexture;
+use plotters_backend::{BackendTextStyle, DrawingErrorKind};
+use texture::{CreateTexture, UpdateTexture};
+
+use crate::cache::{create_texture, update_texture};
+use crate::error::PistonBackendError;
+
+/// Default width and height of the glyph atlas, in pixels: 4 MiB of RGBA.
+pub const DEFAULT_ATLAS_SIZE: u32 = 1024;
+
+/// Transparent pixels kept right of and below each glyph, so that filtering
+/// does not bleed its neighbours into it.
+const GLYPH_PADDING: u32 = 1;
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct FontKey {
+    family: String,
+    style: String,
+    size: u64,
+}
+
+impl FontKey {
+    fn new<TStyle: BackendTextStyle>(style: &TStyle) -> Self {
+        Self {
+            family: style.family().as_str().to_string(),
+            style: style.style().as_str().to_string(),
+            size: style.size().to_bits(),
+        }
+    }
+}
+
+/// A glyph rasterized into the atlas.
+#[derive(Clone, Copy)]
+struct Glyph {
+    /// Position and size in the atlas, `None` for a glyph that draws nothing,
+    /// such as a space.
+    source: Option<[u32; 4]>,
+    /// Top left corner of the glyph, relative to where it is drawn.
+    offset: (i32, i32),
+    /// Width of the glyph alone, from `layout_box`.
+    advance: i32,
+}
+
+/// A quad of a text run: where to draw it, relative to the origin of the run,
+/// and the part of the atlas to draw there.
+pub(crate) struct GlyphQuad {
+    pub rect: Rectangle,
+    pub source: SourceRectangle,
+}
+
+/// Glyph atlas: every glyph drawn so far, rasterized by plotters into one
+/// texture and keyed by font family, font style, size and character.
+///
+/// A text run is drawn as one textured quad per glyph, all from the atlas in a
+/// single submission, so a label that changes every frame, such as a live
+/// readout, only rasterizes and uploads the glyphs it has not used before. The
+/// glyphs are placed where plotters lays them out, kerning included. The atlas
+/// is a coverage mask, white with the coverage in the alpha channel, so it is
+/// tinted with the text colour at draw time. `T` is the texture type of the
+/// piston graphics backend the glyphs are drawn with.
+///
+/// The atlas is `DEFAULT_ATLAS_SIZE` pixels square unless set with
+/// `RenderCache::glyph_atlas_size`. Once it is full it is cleared and filled
+/// again, at the start of the next frame if it has already been drawn from in
+/// this one, since texture updates reach the GPU before the draws of the frame.
+/// Until then the text that does not fit is drawn pixel by pixel.
+pub struct GlyphCache<T = G2dTexture> {
+    texture: Option<T>,
+    size: u32,
+    fonts: HashMap<FontKey, HashMap<char, Glyph>>,
+    /// Top left corner of the free space in the current shelf, and its height.
+    cursor: (u32, u32),
+    shelf_height: u32,
+    used_this_frame: bool,
+    full: bool,
+}
+
+impl<T> Default for GlyphCache<T> {
+    fn default() -> Self {
+        Self {
+            texture: None,
+            size: DEFAULT_ATLAS_SIZE,
+            fonts: HashMap::new(),
+            cursor: (0, 0),
+            shelf_height: 0,
+            used_this_frame: false,
+            full: false,
+        }
+    }
+}
+
+impl<T> GlyphCache<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of glyphs currently in the atlas.
+    pub fn len(&self) -> usize {
+        self.fonts.values().map(HashMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Width and height of the atlas texture, in pixels.
+    pub fn atlas_size(&self) -> u32 {
+        self.size
+    }
+
+    pub(crate) fn texture(&self) -> Option<&T> {
+        self.texture.as_ref()
+    }
+
+    pub(crate) fn set_atlas_size(&mut self, size: u32) {
+        self.size = size;
+        self.texture = None;
+        self.clear();
+    }
+
+    pub(crate) fn begin_frame(&mut self) {
+        if self.full {
+            self.clear();
+        }
+        self.used_this_frame = false;
+    }
+
+    fn clear(&mut self) {
+        self.fonts.clear();
+        self.cursor = (0, 0);
+        self.shelf_height = 0;
+        self.full = false;
+    }
+
+    /// Reserve `width` by `height` pixels, and the padding around them, in the
+    /// shelf being filled or in a new one below it.
+    fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        let (width, height) = (width + GLYPH_PADDING, height + GLYPH_PADDING);
+        if self.cursor.0 + width > self.size {
+            self.cursor = (0, self.cursor.1 + self.shelf_height);
+            self.shelf_height = 0;
+        }
+        if width > self.size || self.cursor.1 + height > self.size {
+            return None;
+        }
+        let at = self.cursor;
+        self.cursor.0 += width;
+        self.shelf_height = self.shelf_height.max(height);
+        Some(at)
+    }
+
+    /// The quads drawing `text`, relative to the point plotters would draw it
+    /// at, after adding the glyphs missing from the atlas. `None` when they do
+    /// not fit in the atlas this frame.
+    pub(crate) fn run<F, TStyle: BackendTextStyle>(
+        &mut self,
+        texture_context: &mut F,
+        text: &str,
+        style: &TStyle,
+    ) -> Result<Option<Vec<GlyphQuad>>, DrawingErrorKind<PistonBackendError>>
+    where
+        T: CreateTexture<F> + UpdateTexture<F>,
+    {
+        let font = FontKey::new(style);
+        let mut glyphs = self.glyphs(texture_context, &font, text, style)?;
+        // Nothing drawn this frame samples the atlas yet, so it can be refilled
+        // from the start of this run
+        if glyphs.is_none() && !self.used_this_frame {
+            self.clear();
+            glyphs = self.glyphs(texture_context, &font, text, style)?;
+        }
+        let glyphs = match glyphs {
+            Some(glyphs) => glyphs,
+            None => {
+                self.full = true;
+                return Ok(None);
+            }
+        };
+        self.used_this_frame = true;
+
+        let ends = text.char_indices().map(|(i, c)| i + c.len_utf8());
+        let mut quads = Vec::with_capacity(glyphs.len());
+        for (end, glyph) in ends.zip(glyphs) {
+            let [x, y, w, h] = match glyph.source {
+                Some(source) => source,
+                None => continue,
+            };
+            // Laying out the text up to and including the glyph keeps the
+            // kerning with the glyph before it
+            let ((min_x, _), (max_x, _)) = style
+                .layout_box(&text[..end])
+                .map_err(|e| DrawingErrorKind::FontError(Box::new(e)))?;
+            let pen = max_x - min_x - glyph.advance;
+            quads.push(GlyphQuad {
+                rect: [
+                    (pen + glyph.offset.0) as f64,
+                    glyph.offset.1 as f64,
+                    w as f64,
+                    h as f64,
+                ],
+                source: [x as f64, y as f64, w as f64, h as f64],
+            });
+        }
+        Ok(Some(quads))
+    }
+
+    /// The glyphs of `text`, rasterized into the atlas when missing, or `None`
+    /// when one of them does not fit.
+    fn glyphs<F, TStyle: BackendTextStyle>(
+        &mut self,
+        texture_context: &mut F,
+        font: &FontKey,
+        text: &str,
+        style: &TStyle,
+    ) -> Result<Option<Vec<Glyph>>, DrawingErrorKind<PistonBackendError>>
+    where
+        T: CreateTexture<F> + UpdateTexture<F>,
+    {
+        let mut glyphs = Vec::with_capacity(text.len());
+        for c in text.chars() {
+            let cached = self.fonts.get(font).and_then(|glyphs| glyphs.get(&c));
+            let glyph = match cached {
+                Some(&glyph) => glyph,
+                None => match self.add(texture_context, font, c, style)? {
+                    Some(glyph) => glyph,
+                    None => return Ok(None),
+                },
+            };
+            glyphs.push(glyph);
+        }
+        Ok(Some(glyphs))
+    }
+
+    /// Rasterize `c` into the atlas, or return `None` when it does not fit.
+    fn add<F, TStyle: BackendTextStyle>(
+        &mut self,
+        texture_context: &mut F,
+        font: &FontKey,
+        c: char,
+        style: &TStyle,
+    ) -> Result<Option<Glyph>, DrawingErrorKind<PistonBackendError>>
+    where
+        T: CreateTexture<F> + UpdateTexture<F>,
+    {
+        let (mask, mut glyph) = rasterize(c, style)?;
+        if let Some(mask) = mask {
+            let (width, height) = (mask.width() - GLYPH_PADDING, mask.height() - GLYPH_PADDING);
+            let (x, y) = match self.allocate(width, height) {
+                Some(at) => at,
+                None => return Ok(None),
+            };
+            if self.texture.is_none() {
+                let blank = RgbaImage::from_pixel(self.size, self.size, Rgba([255, 255, 255, 0]));
+                self.texture = Some(create_texture(texture_context, &blank, "glyph atlas")?);
+            }
+            let texture = self.texture.as_mut().expect("atlas was just created");
+            update_texture(texture, texture_context, &mask, [x, y], "glyph atlas")?;
+            glyph.source = Some([x, y, width, height]);
+        }
+        self.fonts.entry(font.clone()).or_default().insert(c, glyph);
+        Ok(Some(glyph))
+    }
+}
+
+/// Draw `c` with plotters, into a mask with `GLYPH_PADDING` right of and below
+/// it, and return it with the glyph, not yet placed in the atlas. The mask is
+/// `None` when the glyph covers no pixel.
+fn rasterize<TStyle: BackendTextStyle>(
+    c: char,
+    style: &TStyle,
+) -> Result<(Option<RgbaImage>, Glyph), DrawingErrorKind<PistonBackendError>> {
+    let text = c.to_string();
+    let ((min_x, _), (max_x, _)) = style
+        .layout_box(&text)
+        .map_err(|e| DrawingErrorKind::FontError(Box::new(e)))?;
+    let advance = max_x - min_x;
+
+    let alpha = style.color().alpha;
+    let mut coverage = Vec::new();
+    let drawn = style.draw(&text, (0, 0), |x, y, color| {
+        let value = if alpha > 0.0 {
+            color.alpha / alpha
+        } else {
+            0.0
+        };
+        let value = (value.min(1.0) * 255.0).round() as u8;
+        if value > 0 {
+            coverage.push((x, y, value));
+        }
+        Ok::<(), Infallible>(())
+    });
+    if let Err(e) = drawn {
+        return Err(DrawingErrorKind::FontError(Box::new(e)));
+    }
+
+    let (x0, y0, x1, y1) = match coverage.first() {
+        Some(&(x, y, _)) => coverage.iter().fold((x, y, x, y), |b, &(x, y, _)| {
+            (b.0.min(x), b.1.min(y), b.2.max(x), b.3.max(y))
+        }),
+        None => {
+            let glyph = Glyph {
+                source: None,
+                offset: (0, 0),
+                advance,
+            };
+            return Ok((None, glyph));
+        }
+    };
+    let mut mask = RgbaImage::from_pixel(
+        (x1 - x0) as u32 + 1 + GLYPH_PADDING,
+        (y1 - y0) as u32 + 1 + GLYPH_PADDING,
+        Rgba([255, 255, 255, 0]),
+    );
+    for (x, y, value) in coverage {
+        let pixel = mask.get_pixel_mut((x - x0) as u32, (y - y0) as u32);
+        pixel.0[3] = pixel.0[3].max(value);
+    }
+    let glyph = Glyph {
+        source: None,
+        offset: (x0, y0),
+        advance,
+    };
+    Ok((Some(mask), glyph))
+}
@@ -0,0 +1,200 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime};
+
+use image::imageops::{self, FilterType};
+use image::{ImageError, RgbaImage};
+use piston_window::{Event, PistonWindow, RenderEvent};
+
+use crate::error::PistonBackendError;
+use crate::screenshot::{read_frame, timestamp};
+
+/// How `FrameRecorder` writes the frames.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameFormat {
+    /// A single uncompressed YUV 4:4:4 `.y4m` stream. Frames drawn after the
+    /// window is resized are scaled to the size of the first frame.
+    Y4m,
+    /// A directory of `frame-000000.png`, `frame-000001.png`, ...
+    PngSequence,
+}
+
+enum Output {
+    Y4m {
+        writer: BufWriter<File>,
+        size: (u32, u32),
+    },
+    Png,
+}
+
+/// Records the frames of a `draw_piston_window` loop at a fixed frame rate.
+///
+/// Hand every event returned by the loop to `capture`. Frames are timed from the
+/// first render event: when the window renders faster than `fps`, surplus frames
+/// are skipped, and when it renders slower, the last frame is repeated, so the
+/// recording plays back at the speed of the session.
+pub struct FrameRecorder {
+    dir: PathBuf,
+    format: FrameFormat,
+    fps: f64,
+    path: Option<PathBuf>,
+    output: Option<Output>,
+    start: Option<Instant>,
+    frames: u64,
+}
+
+impl FrameRecorder {
+    /// The recording is created in `dir` at the first captured frame, named after
+    /// the current UTC time.
+    pub fn new<P: AsRef<Path>>(dir: P, format: FrameFormat, fps: f64) -> Self {
+        assert!(fps > 0.0, "frame rate must be positive");
+        Self {
+            dir: dir.as_ref().to_path_buf(),
+            format,
+            fps,
+            path: None,
+            output: None,
+            start: None,
+            frames: 0,
+        }
+    }
+
+    /// The `.y4m` file or PNG directory, once the first frame has been captured.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Number of frames written so far.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Write the frame just drawn to `window` as many times as the frame rate
+    /// requires, if `event` is a render event, and return that number.
+    ///
+    /// Must be called right after the `draw_piston_window` function returned
+    /// `event`, like `read_frame`.
+    pub fn capture(
+        &mut self,
+        window: &mut PistonWindow,
+        event: &Event,
+    ) -> Result<u64, PistonBackendError> {
+        if event.render_args().is_none() {
+            return Ok(0);
+        }
+        let now = Instant::now();
+        let start = *self.start.get_or_insert(now);
+        let due = (now.duration_since(start).as_secs_f64() * self.fps).floor() as u64 + 1;
+        if due <= self.frames {
+            return Ok(0);
+        }
+
+        let frame = read_frame(window);
+        let count = due - self.frames;
+        for _ in 0..count {
+            self.write(&frame)?;
+        }
+        Ok(count)
+    }
+
+    /// Flush the recording to disk.
+    pub fn finish(mut self) -> Result<(), PistonBackendError> {
+        if let Some(Output::Y4m { writer, .. }) = self.output.as_mut() {
+            let result = writer.flush();
+            return result.map_err(|e| self.export_error(e));
+        }
+        Ok(())
+    }
+
+    fn export_error(&self, error: io::Error) -> PistonBackendError {
+        PistonBackendError::Export {
+            path: self.path.clone().unwrap_or_else(|| self.dir.clone()),
+            error: ImageError::IoError(error),
+        }
+    }
+
+    fn open(&mut self, frame: &RgbaImage) -> io::Result<Output> {
+        let name = format!("recording-{}", timestamp(SystemTime::now()));
+        match self.format {
+            FrameFormat::Y4m => {
+                let path = self.dir.join(name + ".y4m");
+                self.path = Some(path.clone());
+                let mut writer = BufWriter::new(File::create(path)?);
+                let (width, height) = frame.dimensions();
+                writeln!(
+                    writer,
+                    "YUV4MPEG2 W{} H{} F{} Ip A1:1 C444 XCOLORRANGE=FULL",
+                    width,
+                    height,
+                    frame_rate(self.fps)
+                )?;
+                Ok(Output::Y4m {
+                    writer,
+                    size: (width, height),
+                })
+            }
+            FrameFormat::PngSequence => {
+                let path = self.dir.join(name);
+                self.path = Some(path.clone());
+                fs::create_dir_all(path)?;
+                Ok(Output::Png)
+            }
+        }
+    }
+
+    fn write(&mut self, frame: &RgbaImage) -> Result<(), PistonBackendError> {
+        if self.output.is_none() {
+            let output = self.open(frame).map_err(|e| self.export_error(e))?;
+            self.output = Some(output);
+        }
+        let index = self.frames;
+        let path = self.path.clone().unwrap();
+        let (path, result) = match self.output.as_mut().unwrap() {
+            Output::Y4m { writer, size } => {
+                let result = if frame.dimensions() == *size {
+                    write_y4m_frame(writer, frame)
+                } else {
+                    let scaled = imageops::resize(frame, size.0, size.1, FilterType::Triangle);
+                    write_y4m_frame(writer, &scaled)
+                };
+                (path, result.map_err(ImageError::IoError))
+            }
+            Output::Png => {
+                let path = path.join(format!("frame-{:06}.png", index));
+                let result = frame.save(&path);
+                (path, result)
+            }
+        };
+        result.map_err(|error| PistonBackendError::Export { path, error })?;
+        self.frames += 1;
+        Ok(())
+    }
+}
+
+/// Frame rate as the `num:den` ratio of a Y4M header.
+fn frame_rate(fps: f64) -> String {
+    if fps.fract() == 0.0 {
+        format!("{}:1", fps as u64)
+    } else {
+        format!("{}:1000", (fps * 1000.0).round() as u64)
+    }
+}
+
+/// Write one Y4M frame as full range BT.601 Y, Cb and Cr planes.
+fn write_y4m_frame<W: Write>(writer: &mut W, frame: &RgbaImage) -> io::Result<()> {
+    let pixels = frame.pixels().len();
+    let mut planes = vec![0u8; pixels * 3];
+    let (y, chroma) = planes.split_at_mut(pixels);
+    let (cb, cr) = chroma.split_at_mut(pixels);
+    for (i, pixel) in frame.pixels().enumerate() {
+        let [r, g, b, _] = pixel.0;
+        let (r, g, b) = (r as f32, g as f32, b as f32);
+        let clamp = |v: f32| v.round().clamp(0.0, 255.0) as u8;
+        y[i] = clamp(0.299 * r + 0.587 * g + 0.114 * b);
+        cb[i] = clamp(128.0 - 0.168_736 * r - 0.331_264 * g + 0.5 * b);
+        cr[i] = clamp(128.0 + 0.5 * r - 0.418_688 * g - 0.081_312 * b);
+    }
+    writer.write_all(b"FRAME\n")?;
+    writer.write_all(&planes)
+}
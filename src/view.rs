@@ -0,0 +1,265 @@
+use std::ops::Range;
+
+use piston_window::{
+    Button, CursorEvent, Event, MouseButton, MouseCursorEvent, MouseScrollEvent, PressEvent,
+    ReleaseEvent,
+};
+
+/// Zoom factor of one step of the mouse wheel.
+const ZOOM_STEP: f64 = 1.25;
+/// Smallest visible span, as a fraction of the limits.
+const MIN_SPAN: f64 = 1e-3;
+
+/// Visible axis ranges of a chart, zoomed with the mouse wheel around the cursor
+/// and panned by dragging with the left button.
+///
+/// The chart is drawn with `x_range` and `y_range` as its axis ranges, and tells
+/// the view where its plotting area is with `set_plotting_area`, so that window
+/// positions can be mapped back to chart coordinates:
+///
+/// ```ignore
+/// let scale = backend.scale();
+/// let root = backend.into_drawing_area();
+/// let chart = ChartBuilder::on(&root).build_cartesian_2d(view.x_range(), view.y_range())?;
+/// view.set_plotting_area(chart.plotting_area().get_pixel_range(), scale);
+/// ```
+///
+/// Every input event is then handed to `handle_event`. The axis ranges are only
+/// changed there, so they can be part of the key of a `StaticLayer`.
+#[derive(Clone, Debug)]
+pub struct ChartView {
+    limits: [Range<f64>; 2],
+    visible: [Range<f64>; 2],
+    axes: [bool; 2],
+    area: Option<[Range<f64>; 2]>,
+    scale: f64,
+    cursor: Option<[f64; 2]>,
+    dragging: bool,
+}
+
+impl ChartView {
+    /// Both axes start showing their whole range, `x` and `y`, which zooming and
+    /// panning never go beyond.
+    pub fn new(x: Range<f64>, y: Range<f64>) -> Self {
+        Self {
+            limits: [x.clone(), y.clone()],
+            visible: [x, y],
+            axes: [true, true],
+            area: None,
+            scale: 1.0,
+            cursor: None,
+            dragging: false,
+        }
+    }
+
+    /// Zoom and pan only along the axes that are `true`; the others always show
+    /// their whole range.
+    pub fn with_axes(mut self, x: bool, y: bool) -> Self {
+        self.axes = [x, y];
+        self
+    }
+
+    pub fn x_range(&self) -> Range<f64> {
+        self.visible[0].clone()
+    }
+
+    pub fn y_range(&self) -> Range<f64> {
+        self.visible[1].clone()
+    }
+
+    /// Whether part of the limits is hidden along some axis.
+    pub fn is_zoomed(&self) -> bool {
+        self.visible != self.limits
+    }
+
+    /// Show the whole limits again.
+    pub fn reset(&mut self) {
+        self.visible = self.limits.clone();
+        self.dragging = false;
+    }
+
+    /// Change the limits, for example when the range of the data grows. An axis
+    /// that shows its whole range keeps doing so; a zoomed axis keeps its span
+    /// and is moved back inside the new limits.
+    pub fn set_limits(&mut self, x: Range<f64>, y: Range<f64>) {
+        for (axis, limits) in [x, y].iter().enumerate() {
+            if self.visible[axis] == self.limits[axis] {
+                self.visible[axis] = limits.clone();
+            } else {
+                self.visible[axis] = fit(&self.visible[axis], limits);
+            }
+            self.limits[axis] = limits.clone();
+        }
+    }
+
+    /// Where the chart drew its plotting area, as returned by
+    /// `DrawingArea::get_pixel_range`, and the `scale` of the `PistonBackend` it
+    /// was drawn with.
+    pub fn set_plotting_area(&mut self, area: (Range<i32>, Range<i32>), scale: f64) {
+        let (x, y) = area;
+        self.area = Some([x.start as f64..x.end as f64, y.start as f64..y.end as f64]);
+        self.scale = scale;
+    }
+
+    /// Convert a position in window coordinates, such as the mouse cursor, to
+    /// backend pixels.
+    pub fn window_to_backend(&self, position: [f64; 2]) -> [f64; 2] {
+        [position[0] / self.scale, position[1] / self.scale]
+    }
+
+    /// Chart coordinates at a position in backend pixels, or `None` outside the
+    /// plotting area.
+    pub fn backend_to_chart(&self, position: [f64; 2]) -> Option<(f64, f64)> {
+        let area = self.area.as_ref()?;
+        if !area[0].contains(&position[0]) || !area[1].contains(&position[1]) {
+            return None;
+        }
+        let [x, y] = &self.visible;
+        let tx = (position[0] - area[0].start) / (area[0].end - area[0].start);
+        // Backend pixels grow downwards, chart coordinates upwards
+        let ty = (area[1].end - position[1]) / (area[1].end - area[1].start);
+        Some((
+            x.start + tx * (x.end - x.start),
+            y.start + ty * (y.end - y.start),
+        ))
+    }
+
+    /// Chart coordinates at a position in window coordinates, or `None` outside
+    /// the plotting area.
+    pub fn window_to_chart(&self, position: [f64; 2]) -> Option<(f64, f64)> {
+        self.backend_to_chart(self.window_to_backend(position))
+    }
+
+    /// Position in backend pixels of chart coordinates, or `None` until the
+    /// plotting area is known.
+    pub fn chart_to_backend(&self, (x, y): (f64, f64)) -> Option<[f64; 2]> {
+        let area = self.area.as_ref()?;
+        let [visible_x, visible_y] = &self.visible;
+        let tx = (x - visible_x.start) / (visible_x.end - visible_x.start);
+        let ty = (y - visible_y.start) / (visible_y.end - visible_y.start);
+        Some([
+            area[0].start + tx * (area[0].end - area[0].start),
+            area[1].end - ty * (area[1].end - area[1].start),
+        ])
+    }
+
+    /// Chart coordinates under the mouse cursor, or `None` when it is outside
+    /// the plotting area.
+    pub fn hovered(&self) -> Option<(f64, f64)> {
+        self.window_to_chart(self.cursor?)
+    }
+
+    /// The point of a series nearest to the mouse cursor along the x axis, among
+    /// the points inside the visible x range, as for a spectrum or a time trace.
+    /// `None` when the cursor is outside the plotting area or no point is visible.
+    pub fn snap<I: IntoIterator<Item = (f64, f64)>>(&self, points: I) -> Option<(f64, f64)> {
+        let (x, _) = self.hovered()?;
+        let visible = &self.visible[0];
+        points
+            .into_iter()
+            .filter(|(px, _)| *px >= visible.start && *px <= visible.end)
+            .min_by(|a, b| {
+                let (da, db) = ((a.0 - x).abs(), (b.0 - x).abs());
+                da.partial_cmp(&db).unwrap_or(std::cmp::Ordering::Equal)
+            })
+    }
+
+    /// Last position of the mouse cursor, in window coordinates.
+    pub fn cursor(&self) -> Option<[f64; 2]> {
+        self.cursor
+    }
+
+    /// Zoom or pan according to `event`, and return whether the axis ranges
+    /// changed.
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        if let Some(position) = event.mouse_cursor_args() {
+            let previous = self.cursor.replace(position);
+            return match previous {
+                Some(previous) if self.dragging => self.pan(previous, position),
+                _ => false,
+            };
+        }
+        if let Some(false) = event.cursor_args() {
+            self.cursor = None;
+            self.dragging = false;
+            return false;
+        }
+        if let Some([_, steps]) = event.mouse_scroll_args() {
+            return match self.cursor {
+                Some(cursor) => self.zoom(cursor, ZOOM_STEP.powf(-steps)),
+                None => false,
+            };
+        }
+        if let Some(Button::Mouse(MouseButton::Left)) = event.press_args() {
+            self.dragging = self
+                .cursor
+                .and_then(|cursor| self.window_to_chart(cursor))
+                .is_some();
+        }
+        if let Some(Button::Mouse(MouseButton::Left)) = event.release_args() {
+            self.dragging = false;
+        }
+        false
+    }
+
+    /// Scale the visible spans by `factor` around the chart coordinates under
+    /// `cursor`.
+    fn zoom(&mut self, cursor: [f64; 2], factor: f64) -> bool {
+        let anchors = match self.window_to_chart(cursor) {
+            Some((x, y)) => [x, y],
+            None => return false,
+        };
+        let before = self.visible.clone();
+        for (axis, &anchor) in anchors.iter().enumerate() {
+            if !self.axes[axis] {
+                continue;
+            }
+            let limits = &self.limits[axis];
+            let full = limits.end - limits.start;
+            let visible = &self.visible[axis];
+            let span = visible.end - visible.start;
+            if span <= 0.0 {
+                continue;
+            }
+            let zoomed = (span * factor).clamp(full * MIN_SPAN, full);
+            let start = anchor - (anchor - visible.start) * zoomed / span;
+            self.visible[axis] = fit(&(start..start + zoomed), limits);
+        }
+        self.visible != before
+    }
+
+    /// Move the visible ranges so that the chart follows the cursor from `from`
+    /// to `to`, both in window coordinates.
+    fn pan(&mut self, from: [f64; 2], to: [f64; 2]) -> bool {
+        let area = match &self.area {
+            Some(area) => area.clone(),
+            None => return false,
+        };
+        let (from, to) = (self.window_to_backend(from), self.window_to_backend(to));
+        let before = self.visible.clone();
+        for axis in 0..2 {
+            let pixels = area[axis].end - area[axis].start;
+            if !self.axes[axis] || pixels <= 0.0 {
+                continue;
+            }
+            let visible = &self.visible[axis];
+            let span = visible.end - visible.start;
+            let mut shift = (to[axis] - from[axis]) / pixels * span;
+            if axis == 0 {
+                shift = -shift;
+            }
+            self.visible[axis] = fit(
+                &(visible.start + shift..visible.end + shift),
+                &self.limits[axis],
+            );
+        }
+        self.visible != before
+    }
+}
+
+/// Move `range` inside `limits`, shrinking it to them if it is wider.
+fn fit(range: &Range<f64>, limits: &Range<f64>) -> Range<f64> {
+    let span = (range.end - range.start).min(limits.end - limits.start);
+    let start = range.start.max(limits.start).min(limits.end - span);
+    start..start + span
+}
@@ -0,0 +1,36 @@
+use std::convert::Infallible;
+
+use plotters_backend::{BackendColor, BackendCoord, BackendTextStyle, FontFamily};
+
+/// Text style drawing each character as a 4 by 8 box in the style colour,
+/// black by default, so that the tests do not depend on the fonts installed.
+pub struct BoxText;
+
+impl BackendTextStyle for BoxText {
+    type FontError = Infallible;
+
+    fn family(&self) -> FontFamily<'_> {
+        FontFamily::Monospace
+    }
+
+    fn layout_box(&self, text: &str) -> Result<((i32, i32), (i32, i32)), Infallible> {
+        Ok(((0, 0), (4 * text.chars().count() as i32, 8)))
+    }
+
+    fn draw<E, F: FnMut(i32, i32, BackendColor) -> Result<(), E>>(
+        &self,
+        text: &str,
+        pos: BackendCoord,
+        mut draw: F,
+    ) -> Result<Result<(), E>, Infallible> {
+        let ((x0, y0), (x1, y1)) = self.layout_box(text)?;
+        for y in y0..y1 {
+            for x in x0..x1 {
+                if let Err(e) = draw(pos.0 + x, pos.1 + y, self.color()) {
+                    return Ok(Err(e));
+                }
+            }
+        }
+        Ok(Ok(()))
+    }
+}
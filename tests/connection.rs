@@ -0,0 +1,160 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::{self, Read};
+use std::rc::Rc;
+use std::time::Duration;
+
+use plotters_piston_eeg::{
+    PortAccess, Received, SerialConnection, SerialDevice, SerialSource, SourceCounters,
+    SourceError, AUTO_PORT, KNOWN_BOARDS,
+};
+
+/// A port that returns `reads` in order, then times out.
+struct FakePort {
+    reads: VecDeque<io::Result<Vec<u8>>>,
+}
+
+impl Read for FakePort {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        match self.reads.pop_front() {
+            Some(Ok(bytes)) => {
+                buffer[..bytes.len()].copy_from_slice(&bytes);
+                Ok(bytes.len())
+            }
+            Some(Err(error)) => Err(error),
+            None => Err(io::ErrorKind::TimedOut.into()),
+        }
+    }
+}
+
+/// Devices, and ports handed out in order; `None` is an open that fails, as
+/// while the device is still away.
+#[derive(Default)]
+struct FakePorts {
+    devices: Rc<RefCell<Vec<SerialDevice>>>,
+    ports: VecDeque<Option<FakePort>>,
+    opened: Rc<RefCell<Vec<String>>>,
+}
+
+impl PortAccess for FakePorts {
+    type Port = FakePort;
+
+    fn scan(&mut self) -> Result<Vec<SerialDevice>, SourceError> {
+        Ok(self.devices.borrow().clone())
+    }
+
+    fn open(&mut self, source: &SerialSource) -> Result<FakePort, SourceError> {
+        let port_name = source.port_name().to_owned();
+        if !self
+            .devices
+            .borrow()
+            .iter()
+            .any(|d| d.port_name == port_name)
+        {
+            return Err(SourceError::NoBoard);
+        }
+        self.opened.borrow_mut().push(port_name);
+        self.ports.pop_front().flatten().ok_or(SourceError::NoBoard)
+    }
+}
+
+fn board(port_name: &str) -> SerialDevice {
+    let board = KNOWN_BOARDS[0];
+    SerialDevice {
+        port_name: port_name.to_owned(),
+        vid: board.vid,
+        pid: board.pid.unwrap_or(0),
+        serial_number: Some("EEG-1".to_owned()),
+        manufacturer: None,
+        product: None,
+        board: Some(board),
+    }
+}
+
+fn port(reads: Vec<io::Result<Vec<u8>>>) -> FakePort {
+    FakePort {
+        reads: reads.into(),
+    }
+}
+
+#[test]
+fn transient_errors_do_not_reconnect() {
+    let ports = FakePorts {
+        devices: Rc::new(RefCell::new(vec![board("/dev/ttyACM0")])),
+        ports: vec![Some(port(vec![
+            Err(io::ErrorKind::Interrupted.into()),
+            Ok(b"12\n".to_vec()),
+            Err(io::ErrorKind::WouldBlock.into()),
+            Err(io::ErrorKind::InvalidData.into()),
+        ]))]
+        .into(),
+        ..FakePorts::default()
+    };
+    let opened = ports.opened.clone();
+    let counters = SourceCounters::default();
+    let mut connection =
+        SerialConnection::open_with(ports, SerialSource::new(AUTO_PORT), counters.clone()).unwrap();
+    assert_eq!(connection.port_name(), "/dev/ttyACM0");
+    assert_eq!(connection.serial_number(), Some("EEG-1"));
+
+    let mut buffer = [0; 16];
+    assert_eq!(connection.read(&mut buffer), Received::Bytes(3));
+    assert_eq!(&buffer[..3], b"12\n");
+    assert_eq!(connection.read(&mut buffer), Received::TimedOut);
+    assert_eq!(connection.read(&mut buffer), Received::TimedOut);
+    assert_eq!(connection.read(&mut buffer), Received::TimedOut);
+    assert_eq!(opened.borrow().len(), 1);
+    assert!(counters.gaps().is_empty());
+}
+
+#[test]
+fn lost_device_is_reopened_on_its_new_port() {
+    let devices = Rc::new(RefCell::new(vec![board("/dev/ttyACM0")]));
+    let ports = FakePorts {
+        devices: devices.clone(),
+        ports: vec![
+            Some(port(vec![Err(io::ErrorKind::BrokenPipe.into())])),
+            None,
+            Some(port(vec![Ok(b"7\n".to_vec()), Ok(vec![])])),
+            Some(port(vec![])),
+        ]
+        .into(),
+        ..FakePorts::default()
+    };
+    let opened = ports.opened.clone();
+    let counters = SourceCounters::default();
+    counters.received(5);
+    let mut connection =
+        SerialConnection::open_with(ports, SerialSource::new("/dev/ttyACM0"), counters.clone())
+            .unwrap()
+            .retry_interval(Duration::from_millis(1));
+
+    // The board comes back on another port after being unplugged
+    devices.borrow_mut()[0].port_name = "/dev/ttyACM1".to_owned();
+    let mut buffer = [0; 16];
+    let gap = match connection.read(&mut buffer) {
+        Received::Reconnected(gap) => gap,
+        received => panic!("unexpected {:?}", received),
+    };
+    assert_eq!(gap.samples_before, 5);
+    assert_eq!(counters.gaps(), vec![gap]);
+    assert_eq!(counters.disconnected_since(), None);
+    assert_eq!(connection.port_name(), "/dev/ttyACM1");
+    assert_eq!(connection.read(&mut buffer), Received::Bytes(2));
+
+    // A hung up port reads as the end of the file
+    assert!(matches!(
+        connection.read(&mut buffer),
+        Received::Reconnected(_)
+    ));
+    assert_eq!(counters.gaps().len(), 2);
+    assert_eq!(
+        *opened.borrow(),
+        vec![
+            "/dev/ttyACM0",
+            "/dev/ttyACM1",
+            "/dev/ttyACM1",
+            "/dev/ttyACM1"
+        ]
+    );
+}
@@ -0,0 +1,66 @@
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use plotters_piston_eeg::{Diagnostics, Gap};
+
+#[test]
+fn rates_are_measured_over_a_second() {
+    let mut diagnostics = Diagnostics::new();
+    let counters = diagnostics.counters();
+    let start = Instant::now();
+    diagnostics.frame(start);
+    assert_eq!(diagnostics.fps(), None);
+
+    thread::spawn(move || {
+        counters.received(500);
+        counters.parse_error();
+        counters.dropped(3);
+    })
+    .join()
+    .unwrap();
+    diagnostics.frame(start + Duration::from_millis(500));
+    diagnostics.counters().received(500);
+    diagnostics.frame(start + Duration::from_secs(1));
+
+    assert_eq!(diagnostics.fps(), Some(3.0));
+    assert_eq!(diagnostics.sample_rate(), Some(1000.0));
+    assert_eq!(diagnostics.counters().totals(), (1000, 1, 3));
+}
+
+#[test]
+fn panel_lists_backlog_and_processing_time() {
+    let mut diagnostics = Diagnostics::new();
+    diagnostics.set_backlog(1024, 4096);
+    assert_eq!(diagnostics.measure(|| 42), 42);
+
+    let lines = diagnostics.lines();
+    let value = |label| &lines.iter().find(|line| line.0 == label).unwrap().1;
+    assert_eq!(value("Backlog"), "1024 / 4096 (25%)");
+    assert_eq!(value("FPS"), "-");
+    assert!(value("DSP time").ends_with(" ms"));
+}
+
+#[test]
+fn disconnection_and_gaps_are_reported() {
+    let diagnostics = Diagnostics::new();
+    let counters = diagnostics.counters();
+    let value = |label| {
+        let lines = diagnostics.lines();
+        lines.into_iter().find(|line| line.0 == label).unwrap().1
+    };
+    assert_eq!(value("Connection"), "connected");
+    assert_eq!(value("Gaps"), "0 (0.0 s)");
+
+    counters.received(100);
+    counters.disconnected(Instant::now());
+    assert!(value("Connection").starts_with("lost for "));
+    counters.reconnected(Gap {
+        lost_at: SystemTime::now(),
+        duration: Duration::from_millis(2500),
+        samples_before: 100,
+    });
+    assert_eq!(counters.disconnected_since(), None);
+    assert_eq!(counters.gaps()[0].samples_before, 100);
+    assert_eq!(value("Connection"), "connected");
+    assert_eq!(value("Gaps"), "1 (2.5 s)");
+}
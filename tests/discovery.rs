@@ -0,0 +1,91 @@
+use std::io;
+
+use plotters_piston_eeg::{Discovery, PortProblem, SerialDevice};
+use serialport::{ErrorKind, SerialPortInfo, SerialPortType, UsbPortInfo};
+
+fn usb_port(port_name: &str, vid: u16, pid: u16, serial_number: &str) -> SerialPortInfo {
+    SerialPortInfo {
+        port_name: port_name.to_string(),
+        port_type: SerialPortType::UsbPort(UsbPortInfo {
+            vid,
+            pid,
+            serial_number: Some(serial_number.to_string()),
+            manufacturer: None,
+            product: None,
+        }),
+    }
+}
+
+#[test]
+fn known_boards_are_recognised() {
+    let devices = SerialDevice::from_ports(vec![
+        usb_port("/dev/ttyUSB0", 0x0403, 0x6015, "DQ0081"),
+        usb_port("/dev/ttyUSB1", 0x0403, 0x6001, "A9007"),
+        usb_port("/dev/ttyUSB2", 0x1a86, 0x7523, "1"),
+        usb_port("/dev/ttyACM0", 0x2341, 0x0043, "7573"),
+        usb_port("/dev/ttyACM1", 0x046d, 0xc52b, "K"),
+        SerialPortInfo {
+            port_name: "/dev/ttyS0".to_string(),
+            port_type: SerialPortType::Unknown,
+        },
+    ]);
+    let boards: Vec<_> = devices
+        .iter()
+        .map(|device| device.board.map(|board| board.name))
+        .collect();
+    assert_eq!(
+        boards,
+        vec![
+            Some("OpenBCI dongle"),
+            Some("FTDI USB serial"),
+            Some("Arduino Uno/Nano clone (CH340)"),
+            Some("Arduino (native USB ACM)"),
+            None,
+        ]
+    );
+    assert_eq!(
+        devices[3].to_string(),
+        "/dev/ttyACM0 (2341:0043, Arduino (native USB ACM), serial number 7573)"
+    );
+}
+
+#[test]
+fn one_board_is_picked_and_several_offered() {
+    let single = SerialDevice::from_ports(vec![
+        usb_port("/dev/ttyACM1", 0x046d, 0xc52b, "K"),
+        usb_port("/dev/cu.usbmodem1401", 0x2341, 0x0043, "7573"),
+        usb_port("/dev/tty.usbmodem1401", 0x2341, 0x0043, "7573"),
+    ]);
+    match Discovery::from_devices(single) {
+        Discovery::Found(device) => assert_eq!(device.port_name, "/dev/cu.usbmodem1401"),
+        other => panic!("expected one board, got {:?}", other),
+    }
+
+    let several = SerialDevice::from_ports(vec![
+        usb_port("/dev/ttyUSB0", 0x1a86, 0x7523, "1"),
+        usb_port("/dev/ttyACM0", 0x2341, 0x0043, "7573"),
+    ]);
+    match Discovery::from_devices(several) {
+        Discovery::Several(devices) => assert_eq!(devices.len(), 2),
+        other => panic!("expected several boards, got {:?}", other),
+    }
+    assert_eq!(Discovery::from_devices(Vec::new()), Discovery::NotFound);
+}
+
+#[test]
+fn open_failures_are_explained() {
+    let problem = |kind| PortProblem::of(&serialport::Error::new(kind, "failed"));
+    assert_eq!(
+        problem(ErrorKind::Io(io::ErrorKind::PermissionDenied)),
+        PortProblem::PermissionDenied
+    );
+    assert_eq!(problem(ErrorKind::NoDevice), PortProblem::Unavailable);
+    assert_eq!(
+        problem(ErrorKind::Io(io::ErrorKind::NotFound)),
+        PortProblem::NotFound
+    );
+    assert_eq!(
+        problem(ErrorKind::InvalidInput),
+        PortProblem::Other("failed".to_string())
+    );
+}
@@ -0,0 +1,66 @@
+use plotters_piston_eeg::{crc16, FrameDecoder, FrameEncoder, FrameStats};
+
+fn samples(frame: u16) -> Vec<u16> {
+    (0..32).map(|i| (frame * 32 + i) % 1024).collect()
+}
+
+#[test]
+fn checksum_is_crc16_ccitt() {
+    assert_eq!(crc16(b"123456789"), 0x29b1);
+    assert_eq!(crc16(&[]), 0xffff);
+}
+
+#[test]
+fn frames_split_anywhere_are_decoded() {
+    let mut encoder = FrameEncoder::new();
+    let stream: Vec<u8> = (0..300).flat_map(|i| encoder.encode(&samples(i))).collect();
+    assert_eq!(stream.len(), 300 * 70);
+
+    let mut decoder = FrameDecoder::new();
+    let mut frames = Vec::new();
+    for chunk in stream.chunks(17) {
+        frames.extend(decoder.push(chunk));
+    }
+    assert_eq!(frames.len(), 300);
+    assert_eq!(frames[299].sequence, 43);
+    assert!(frames.iter().all(|frame| frame.lost == 0));
+    let decoded: Vec<u16> = frames.into_iter().flat_map(|frame| frame.samples).collect();
+    let sent: Vec<u16> = (0..300).flat_map(samples).collect();
+    assert_eq!(decoded, sent);
+}
+
+#[test]
+fn lost_and_corrupted_frames_are_reported() {
+    let mut encoder = FrameEncoder::new();
+    let mut stream = b"\x5a\xa5garbage at start-up\xa5".to_vec();
+    for i in 0..10 {
+        let mut frame = encoder.encode(&samples(i));
+        match i {
+            // Lost on the way
+            3 | 4 => continue,
+            // A flipped bit in a sample
+            7 => frame[10] ^= 0x04,
+            _ => {}
+        }
+        stream.extend(frame);
+    }
+
+    let mut decoder = FrameDecoder::new();
+    let frames = decoder.push(&stream);
+    let received: Vec<(u8, u8)> = frames.iter().map(|f| (f.sequence, f.lost)).collect();
+    assert_eq!(
+        received,
+        vec![(0, 0), (1, 0), (2, 0), (5, 2), (6, 0), (8, 1), (9, 0)]
+    );
+    assert_eq!(frames[3].samples, samples(5));
+    let stats = decoder.stats();
+    assert_eq!(stats.frames, 7);
+    assert_eq!(stats.lost, 3);
+    assert_eq!(stats.corrupted, 1);
+    assert_eq!(stats.skipped_bytes, 22 + 70);
+
+    decoder.reset();
+    let frames = decoder.push(&encoder.encode(&[1, 2, 3]));
+    assert_eq!(frames[0].lost, 0);
+    assert_ne!(decoder.stats(), FrameStats::default());
+}
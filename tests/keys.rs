@@ -0,0 +1,71 @@
+use piston_window::{Button, ButtonArgs, ButtonState, Event, Input, Key};
+use plotters_piston_eeg::{KeyBindings, RecordingGraphics};
+
+mod common;
+
+use common::BoxText;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Command {
+    Freeze,
+    NextWindow,
+}
+
+fn press(key: Key) -> Event {
+    Event::Input(
+        Input::Button(ButtonArgs {
+            state: ButtonState::Press,
+            button: Button::Keyboard(key),
+            scancode: None,
+        }),
+        None,
+    )
+}
+
+fn bindings() -> KeyBindings<Command> {
+    KeyBindings::new()
+        .bind(Key::Space, Command::Freeze, "freeze")
+        .bind(Key::W, Command::NextWindow, "window")
+        .help(Key::F1, "help")
+}
+
+#[test]
+fn pressed_keys_become_commands() {
+    let mut keys = bindings();
+    assert_eq!(keys.command(&press(Key::W)), Some(Command::NextWindow));
+    assert_eq!(keys.command(&press(Key::Space)), Some(Command::Freeze));
+    assert_eq!(keys.command(&press(Key::Q)), None);
+
+    let mut keys = keys.bind(Key::W, Command::Freeze, "freeze too");
+    assert_eq!(keys.command(&press(Key::W)), Some(Command::Freeze));
+    assert_eq!(
+        keys.help_lines(),
+        vec![
+            ("Space".to_string(), "freeze"),
+            ("W".to_string(), "freeze too"),
+            ("F1".to_string(), "help"),
+        ]
+    );
+}
+
+#[test]
+fn help_key_toggles_the_help_panel() {
+    let mut keys = bindings();
+    let mut graphics = RecordingGraphics::new(320, 240);
+
+    keys.draw_help(&mut graphics.backend(), &BoxText).unwrap();
+    assert!(graphics.calls().is_empty());
+
+    assert_eq!(keys.command(&press(Key::F1)), None);
+    assert!(keys.help_visible());
+    keys.draw_help(&mut graphics.backend(), &BoxText).unwrap();
+    assert!(!graphics.calls().is_empty());
+    // "Space" in the first row, inside the panel at the top left corner
+    let image = graphics.image();
+    assert_eq!(image.get_pixel(25, 25).0, [0, 0, 0, 255]);
+    assert!(image.get_pixel(50, 60).0[3] > 0);
+    assert_eq!(image.get_pixel(100, 100).0, [0, 0, 0, 0]);
+
+    keys.command(&press(Key::F1));
+    assert!(!keys.help_visible());
+}
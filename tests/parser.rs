@@ -0,0 +1,69 @@
+use plotters_piston_eeg::{LineParser, ParseError, ParseErrors};
+
+fn samples(parsed: Vec<Result<isize, ParseError>>) -> Vec<isize> {
+    parsed.into_iter().filter_map(Result::ok).collect()
+}
+
+#[test]
+fn malformed_lines_are_skipped_and_counted() {
+    let mut parser = LineParser::<isize>::new();
+    let mut values = Vec::new();
+    // Opened in the middle of "1023", with a reset of the board halfway
+    for chunk in [
+        &b"23\r\n512\r\n\xff\xfe\x00\r\n5"[..],
+        b"1\r\n\r\n1a\r\n-7\r\n\x1b[0m\r\n",
+        b"10",
+        b"23\r\n",
+    ]
+    .iter()
+    {
+        values.extend(samples(parser.push(chunk)));
+    }
+    assert_eq!(values, vec![512, 51, -7, 1023]);
+    assert_eq!(
+        parser.errors(),
+        ParseErrors {
+            partial: 1,
+            invalid_text: 2,
+            not_a_number: 1,
+            too_long: 0,
+        }
+    );
+    assert_eq!(parser.errors().total(), 4);
+}
+
+#[test]
+fn long_garbage_resynchronises_on_the_next_line() {
+    let mut parser = LineParser::<isize>::new().max_line(8);
+    assert_eq!(parser.push(b"\n"), vec![]);
+    assert_eq!(parser.push(&[b'7'; 1000]), vec![]);
+    assert_eq!(
+        parser.push(b"77\n300\n"),
+        vec![Err(ParseError::TooLong), Ok(300)]
+    );
+
+    parser.push(b"40");
+    parser.reset();
+    assert_eq!(
+        parser.push(b"96\n128\n"),
+        vec![Err(ParseError::Partial), Ok(128)]
+    );
+}
+
+#[test]
+fn random_bytes_never_stop_the_parser() {
+    let mut parser = LineParser::<isize>::new();
+    let mut state: u32 = 12345;
+    for round in 0..200 {
+        let garbage: Vec<u8> = (0..round % 50)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (state >> 16) as u8
+            })
+            .collect();
+        parser.push(&garbage);
+        // A sample after a line boundary is read, whatever came before it
+        assert_eq!(parser.push(b"\n42\n").last(), Some(&Ok(42)));
+    }
+    assert!(parser.errors().total() > 0);
+}
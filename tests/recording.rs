@@ -0,0 +1,404 @@
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use image::RgbaImage;
+use plotters::prelude::*;
+use plotters_backend::{BackendColor, DrawingBackend, DrawingErrorKind};
+use plotters_piston_eeg::{
+    DrawCall, FillGradient, LinePattern, PistonBackendError, RecordedTexture, RecordingGraphics,
+    RenderCache,
+};
+
+mod common;
+
+use common::BoxText;
+
+const RED_PIXEL: BackendColor = BackendColor {
+    alpha: 1.0,
+    rgb: (255, 0, 0),
+};
+
+const BLUE_PIXEL: BackendColor = BackendColor {
+    alpha: 1.0,
+    rgb: (0, 0, 255),
+};
+
+fn quad(x: f32, y: f32, w: f32, h: f32) -> Vec<[f32; 2]> {
+    vec![
+        [x, y],
+        [x + w, y],
+        [x + w, y + h],
+        [x, y],
+        [x + w, y + h],
+        [x, y + h],
+    ]
+}
+
+/// Compare `image` with `tests/golden/<name>.png`. The golden file is only
+/// written when `UPDATE_GOLDEN` is set, so that a missing file fails the test
+/// instead of passing it.
+fn assert_golden(name: &str, image: &RgbaImage) {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden");
+    let path = dir.join(format!("{}.png", name));
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(&dir).unwrap();
+        image.save(&path).unwrap();
+        return;
+    }
+    assert!(
+        path.exists(),
+        "missing golden image {}; run the tests with UPDATE_GOLDEN=1 to create it",
+        path.display()
+    );
+    let golden = image::open(&path).unwrap().to_rgba8();
+    assert_eq!(golden.dimensions(), image.dimensions(), "size of {}", name);
+    let differing = golden
+        .pixels()
+        .zip(image.pixels())
+        .filter(|(a, b)| a != b)
+        .count();
+    assert_eq!(
+        differing,
+        0,
+        "{} pixels differ from {}",
+        differing,
+        path.display()
+    );
+}
+
+#[test]
+fn pixel_is_recorded_in_window_pixels() {
+    let mut graphics = RecordingGraphics::new(8, 8);
+    {
+        let mut backend = graphics.backend();
+        backend.draw_pixel((3, 4), RED_PIXEL).unwrap();
+        backend.present().unwrap();
+    }
+    assert_eq!(
+        graphics.calls(),
+        &[DrawCall::Triangles {
+            positions: quad(3.0, 4.0, 1.0, 1.0),
+            colors: vec![[1.0, 0.0, 0.0, 1.0]; 6],
+        }]
+    );
+    assert_eq!(graphics.image().get_pixel(3, 4).0, [255, 0, 0, 255]);
+    assert_eq!(graphics.image().get_pixel(4, 4).0, [0, 0, 0, 0]);
+}
+
+#[test]
+fn solid_primitives_share_one_submission() {
+    let mut graphics = RecordingGraphics::new(32, 32);
+    {
+        let mut backend = graphics.backend();
+        backend.draw_line((0, 0), (10, 10), &RED_PIXEL).unwrap();
+        backend
+            .draw_rect((2, 2), (8, 8), &RED_PIXEL, false)
+            .unwrap();
+        backend.draw_circle((16, 16), 5, &RED_PIXEL, true).unwrap();
+        backend
+            .fill_polygon(vec![(20, 20), (30, 20), (25, 30)], &RED_PIXEL)
+            .unwrap();
+    }
+    assert_eq!(graphics.calls().len(), 1);
+    match &graphics.calls()[0] {
+        DrawCall::Triangles { positions, colors } => {
+            assert_eq!(positions.len() % 3, 0);
+            assert_eq!(positions.len(), colors.len());
+        }
+        call => panic!("unexpected {:?}", call),
+    }
+}
+
+#[test]
+fn unfilled_circles_are_drawn_as_rings() {
+    for &radius in &[3, 10, 12, 40, 100] {
+        let mut graphics = RecordingGraphics::new(256, 256);
+        graphics
+            .backend()
+            .draw_circle((128, 128), radius, &RED_PIXEL, false)
+            .unwrap();
+        let image = graphics.image();
+        let red = image.pixels().filter(|p| p.0 == [255, 0, 0, 255]).count();
+        assert!(red > 0, "nothing drawn for radius {}", radius);
+        assert_eq!(
+            image.get_pixel(128, 128).0,
+            [0, 0, 0, 0],
+            "radius {}",
+            radius
+        );
+    }
+}
+
+#[test]
+fn translucent_rect_is_blended_once_per_pixel() {
+    let mut graphics = RecordingGraphics::new(8, 8);
+    let half_red = BackendColor {
+        alpha: 0.5,
+        rgb: (255, 0, 0),
+    };
+    graphics
+        .backend()
+        .draw_rect((1, 1), (7, 7), &half_red, true)
+        .unwrap();
+    for y in 0..8 {
+        for x in 0..8 {
+            let expected = if (1..7).contains(&x) && (1..7).contains(&y) {
+                [255, 0, 0, 128]
+            } else {
+                [0, 0, 0, 0]
+            };
+            assert_eq!(
+                graphics.image().get_pixel(x, y).0,
+                expected,
+                "({}, {})",
+                x,
+                y
+            );
+        }
+    }
+}
+
+#[test]
+fn cached_bitmap_is_drawn_after_pending_primitives() {
+    let mut graphics = RecordingGraphics::new(8, 8);
+    let mut cache = RenderCache::<RecordedTexture, ()>::with_texture_context(());
+    {
+        let mut backend = graphics.backend().with_cache(&mut cache);
+        backend.draw_rect((0, 0), (2, 2), &RED_PIXEL, true).unwrap();
+        backend
+            .blit_bitmap((4, 4), (2, 2), &[0, 0, 255].repeat(4))
+            .unwrap();
+    }
+    let calls = graphics.calls();
+    assert_eq!(calls.len(), 2);
+    assert!(matches!(calls[0], DrawCall::Triangles { .. }));
+    match &calls[1] {
+        DrawCall::Textured {
+            positions,
+            texture_size,
+            ..
+        } => {
+            assert_eq!(*texture_size, (2, 2));
+            assert_eq!(positions.len(), 6);
+            assert!(positions
+                .iter()
+                .all(|&[x, y]| (x == 4.0 || x == 6.0) && (y == 4.0 || y == 6.0)));
+        }
+        call => panic!("unexpected {:?}", call),
+    }
+    assert_eq!(graphics.image().get_pixel(1, 1).0, [255, 0, 0, 255]);
+    assert_eq!(graphics.image().get_pixel(5, 5).0, [0, 0, 255, 255]);
+}
+
+fn is_textured(call: &DrawCall) -> bool {
+    matches!(call, DrawCall::Textured { .. })
+}
+
+#[test]
+fn changing_label_reuses_the_glyph_atlas() {
+    let mut graphics = RecordingGraphics::new(64, 16);
+    let mut cache =
+        RenderCache::<RecordedTexture, ()>::with_texture_context(()).glyph_atlas_size(64);
+    for i in 0..10 {
+        cache.begin_frame();
+        graphics
+            .backend()
+            .with_cache(&mut cache)
+            .draw_text(&format!("label {:02}", i), &BoxText, (0, 0))
+            .unwrap();
+        let calls = graphics.take_calls();
+        assert_eq!(calls.len(), 1);
+        match &calls[0] {
+            DrawCall::Textured {
+                positions,
+                texture_size,
+                ..
+            } => {
+                assert_eq!(*texture_size, (64, 64));
+                assert_eq!(positions.len(), 6 * 8);
+            }
+            call => panic!("unexpected {:?}", call),
+        }
+    }
+    // "l", "a", "b", "e", " " and the ten digits
+    assert_eq!(cache.glyphs().len(), 15);
+
+    let mut uncached = RecordingGraphics::new(64, 16);
+    uncached
+        .backend()
+        .draw_text("label 09", &BoxText, (0, 0))
+        .unwrap();
+    assert_eq!(graphics.image(), uncached.image());
+}
+
+#[test]
+fn full_glyph_atlas_is_refilled_next_frame() {
+    // Room for three glyphs of 4 by 8 pixels and their padding
+    let mut graphics = RecordingGraphics::new(16, 8);
+    let mut cache =
+        RenderCache::<RecordedTexture, ()>::with_texture_context(()).glyph_atlas_size(16);
+    cache.begin_frame();
+    {
+        let mut backend = graphics.backend().with_cache(&mut cache);
+        backend.draw_text("ab", &BoxText, (0, 0)).unwrap();
+        backend.draw_text("cd", &BoxText, (8, 0)).unwrap();
+    }
+    let calls = graphics.take_calls();
+    assert_eq!(calls.len(), 2);
+    assert!(is_textured(&calls[0]));
+    assert!(!is_textured(&calls[1]));
+    assert_eq!(cache.glyphs().len(), 3);
+    assert!(graphics.image().pixels().all(|p| p.0[3] == 255));
+
+    cache.begin_frame();
+    assert!(cache.glyphs().is_empty());
+    graphics
+        .backend()
+        .with_cache(&mut cache)
+        .draw_text("cd", &BoxText, (8, 0))
+        .unwrap();
+    assert!(is_textured(&graphics.take_calls()[0]));
+    assert_eq!(cache.glyphs().len(), 2);
+
+    // A run that never fits is drawn pixel by pixel
+    cache.begin_frame();
+    graphics
+        .backend()
+        .with_cache(&mut cache)
+        .draw_text("wxyz", &BoxText, (0, 0))
+        .unwrap();
+    assert!(!is_textured(&graphics.take_calls()[0]));
+}
+
+#[test]
+fn short_bitmap_is_rejected() {
+    let mut graphics = RecordingGraphics::new(8, 8);
+    let result = graphics.backend().blit_bitmap((0, 0), (2, 2), &[0; 6]);
+    assert!(matches!(
+        result,
+        Err(DrawingErrorKind::DrawingError(
+            PistonBackendError::BitmapSize {
+                width: 2,
+                height: 2,
+                len: 6
+            }
+        ))
+    ));
+}
+
+#[test]
+fn dashed_line_leaves_gaps() {
+    let mut graphics = RecordingGraphics::new(20, 4);
+    {
+        let mut backend = graphics.backend();
+        backend.line_pattern().set(LinePattern::Dashed {
+            dash: 4.0,
+            gap: 2.0,
+        });
+        backend.draw_line((0, 2), (20, 2), &RED_PIXEL).unwrap();
+    }
+    let row: Vec<bool> = (0..20)
+        .map(|x| {
+            graphics.image().get_pixel(x, 1).0[3] > 0 || graphics.image().get_pixel(x, 2).0[3] > 0
+        })
+        .collect();
+    let expected: Vec<bool> = (0..20).map(|x| x % 6 < 4).collect();
+    assert_eq!(row, expected);
+}
+
+#[test]
+fn gradient_bands_have_sharp_edges() {
+    let mut graphics = RecordingGraphics::new(12, 4);
+    {
+        let mut backend = graphics.backend();
+        backend
+            .fill_gradient()
+            .set(Some(FillGradient::horizontal(vec![
+                (5.0, RED_PIXEL),
+                (5.0, BLUE_PIXEL),
+            ])));
+        backend
+            .fill_polygon(vec![(0, 0), (12, 0), (12, 4), (0, 4)], &RED_PIXEL)
+            .unwrap();
+    }
+    for x in 0..12 {
+        let expected = if x < 5 {
+            [255, 0, 0, 255]
+        } else {
+            [0, 0, 255, 255]
+        };
+        assert_eq!(graphics.image().get_pixel(x, 2).0, expected, "x = {}", x);
+    }
+}
+
+#[test]
+fn gradient_without_stops_fills_with_the_style_colour() {
+    let mut graphics = RecordingGraphics::new(4, 4);
+    {
+        let mut backend = graphics.backend();
+        backend
+            .fill_gradient()
+            .set(Some(FillGradient::horizontal(vec![])));
+        backend
+            .fill_polygon(vec![(0, 0), (4, 0), (4, 4), (0, 4)], &BLUE_PIXEL)
+            .unwrap();
+    }
+    assert_eq!(graphics.image().get_pixel(2, 2).0, [0, 0, 255, 255]);
+}
+
+#[test]
+fn clip_rectangle_limits_drawing() {
+    let mut graphics = RecordingGraphics::new(10, 10);
+    {
+        let mut backend = graphics.backend();
+        let clip = backend.clip();
+        backend
+            .draw_rect((0, 0), (10, 10), &RED_PIXEL, true)
+            .unwrap();
+        clip.clipped((2..5, 3..6), || {
+            backend
+                .draw_rect((0, 3), (10, 6), &BLUE_PIXEL, true)
+                .unwrap();
+        });
+        backend.draw_pixel((9, 4), BLUE_PIXEL).unwrap();
+    }
+    assert_eq!(graphics.calls().len(), 3);
+    for x in 0..10 {
+        let expected = if (2..5).contains(&x) || x == 9 {
+            [0, 0, 255, 255]
+        } else {
+            [255, 0, 0, 255]
+        };
+        assert_eq!(graphics.image().get_pixel(x, 4).0, expected, "x = {}", x);
+    }
+}
+
+fn draw_sine(graphics: &mut RecordingGraphics) -> Result<(), Box<dyn Error>> {
+    let root = graphics.backend().into_drawing_area();
+    root.fill(&WHITE)?;
+    let mut chart = ChartBuilder::on(&root)
+        .margin(10)
+        .build_cartesian_2d(0f32..10f32, -1.2f32..1.2f32)?;
+    chart.draw_series(AreaSeries::new(
+        (0..=100).map(|i| (i as f32 / 10.0, (i as f32 / 10.0).sin())),
+        0.0,
+        BLUE.mix(0.3),
+    ))?;
+    chart.draw_series(LineSeries::new(
+        (0..=100).map(|i| (i as f32 / 10.0, (i as f32 / 10.0).sin())),
+        RED.stroke_width(2),
+    ))?;
+    root.present()?;
+    Ok(())
+}
+
+#[test]
+fn sine_chart_matches_golden_image() {
+    let mut graphics = RecordingGraphics::new(160, 120);
+    draw_sine(&mut graphics).unwrap();
+    assert_golden("sine_chart", graphics.image());
+}
@@ -0,0 +1,66 @@
+use std::env;
+use std::fs;
+use std::time::Duration;
+
+use plotters_piston_eeg::{Protocol, SerialSource};
+use serialport::{DataBits, FlowControl, Parity, StopBits};
+
+#[test]
+fn flags_override_the_config_file() {
+    let config = env::temp_dir().join("plotters-piston-eeg-serial.conf");
+    fs::write(
+        &config,
+        "# Uno do laboratório\nport = /dev/ttyACM0 # USB nativo\nbaud=9600\nparity = even\n\nflow_control = hardware\n",
+    )
+    .unwrap();
+
+    let source = SerialSource::from_args([
+        "--baud=230400",
+        "--config",
+        config.to_str().unwrap(),
+        "--timeout-ms",
+        "25",
+        "--data-bits",
+        "7",
+        "--stop-bits=2",
+    ])
+    .unwrap();
+    assert_eq!(
+        source,
+        SerialSource::new("/dev/ttyACM0")
+            .baud_rate(230_400)
+            .parity(Parity::Even)
+            .flow_control(FlowControl::Hardware)
+            .timeout(Duration::from_millis(25))
+            .data_bits(DataBits::Seven)
+            .stop_bits(StopBits::Two)
+    );
+    assert_eq!(
+        SerialSource::from_args(Vec::<String>::new()).unwrap(),
+        SerialSource::default()
+    );
+}
+
+#[test]
+fn invalid_settings_are_reported() {
+    let error = |args: &[&str]| SerialSource::from_args(args).unwrap_err().to_string();
+    assert_eq!(error(&["--baud"]), "missing value for baud");
+    assert_eq!(
+        error(&["--parity", "mark"]),
+        "invalid value \"mark\" for parity"
+    );
+    assert_eq!(error(&["--speed=fast"]), "invalid value \"fast\" for speed");
+    assert_eq!(
+        error(&["ttyUSB0"]),
+        "invalid value \"ttyUSB0\" for argument"
+    );
+    assert!(SerialSource::default().with_config("baud 9600").is_err());
+}
+
+#[test]
+fn protocol_is_chosen_like_the_port_settings() {
+    assert_eq!(SerialSource::default().wire_protocol(), Protocol::Text);
+    let source = SerialSource::from_args(["--protocol", "binary"]).unwrap();
+    assert_eq!(source.wire_protocol(), Protocol::Binary);
+    assert!(SerialSource::from_args(["--protocol=morse"]).is_err());
+}
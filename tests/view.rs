@@ -0,0 +1,90 @@
+use piston_window::{Button, ButtonArgs, ButtonState, Event, Input, Motion, MouseButton};
+use plotters_piston_eeg::ChartView;
+
+fn cursor(x: f64, y: f64) -> Event {
+    Event::Input(Input::Move(Motion::MouseCursor([x, y])), None)
+}
+
+fn scroll(steps: f64) -> Event {
+    Event::Input(Input::Move(Motion::MouseScroll([0.0, steps])), None)
+}
+
+fn left_button(state: ButtonState) -> Event {
+    Event::Input(
+        Input::Button(ButtonArgs {
+            state,
+            button: Button::Mouse(MouseButton::Left),
+            scancode: None,
+        }),
+        None,
+    )
+}
+
+/// A 160 x 100 chart plotted over backend pixels 100..900 x 40..440, on a
+/// window with two backend pixels per window coordinate.
+fn spectrum_view() -> ChartView {
+    let mut view = ChartView::new(0.0..160.0, 0.0..100.0).with_axes(true, false);
+    view.set_plotting_area((100..900, 40..440), 0.5);
+    view
+}
+
+#[test]
+fn window_positions_map_to_chart_coordinates() {
+    let view = spectrum_view();
+    assert_eq!(view.window_to_backend([250.0, 120.0]), [500.0, 240.0]);
+    assert_eq!(view.window_to_chart([50.0, 120.0]), Some((0.0, 50.0)));
+    assert_eq!(view.window_to_chart([250.0, 120.0]), Some((80.0, 50.0)));
+    assert_eq!(view.window_to_chart([40.0, 120.0]), None);
+}
+
+#[test]
+fn wheel_zooms_around_the_cursor() {
+    let mut view = spectrum_view();
+    assert!(!view.handle_event(&cursor(250.0, 120.0)));
+    assert!(view.handle_event(&scroll(1.0)));
+    assert!(view.x_range().end - view.x_range().start < 160.0);
+    assert_eq!(view.y_range(), 0.0..100.0);
+    let (x, _) = view.window_to_chart([250.0, 120.0]).unwrap();
+    assert!((x - 80.0).abs() < 1e-9);
+
+    for _ in 0..10 {
+        view.handle_event(&scroll(-1.0));
+    }
+    assert!(!view.is_zoomed());
+}
+
+#[test]
+fn drag_pans_within_the_limits() {
+    let mut view = spectrum_view();
+    view.handle_event(&cursor(250.0, 120.0));
+    view.handle_event(&scroll(1.0));
+    view.handle_event(&scroll(1.0));
+    let span = view.x_range().end - view.x_range().start;
+
+    view.handle_event(&left_button(ButtonState::Press));
+    assert!(view.handle_event(&cursor(150.0, 120.0)));
+    assert!(view.x_range().start > 80.0 - span / 2.0);
+    for x in (0..15).map(|i| 140.0 - 10.0 * i as f64) {
+        view.handle_event(&cursor(x, 120.0));
+    }
+    assert_eq!(view.x_range().end, 160.0);
+    assert!((view.x_range().end - view.x_range().start - span).abs() < 1e-9);
+
+    view.handle_event(&left_button(ButtonState::Release));
+    assert!(!view.handle_event(&cursor(400.0, 120.0)));
+}
+
+#[test]
+fn hovered_point_snaps_to_the_nearest_visible_sample() {
+    let mut view = spectrum_view();
+    let samples: Vec<(f64, f64)> = (0..=16).map(|i| (10.0 * i as f64, i as f64)).collect();
+    assert_eq!(view.snap(samples.iter().cloned()), None);
+
+    view.handle_event(&cursor(251.0, 60.0));
+    assert_eq!(view.snap(samples.iter().cloned()), Some((80.0, 8.0)));
+    assert_eq!(view.chart_to_backend((80.0, 25.0)), Some([500.0, 340.0]));
+
+    view.handle_event(&cursor(40.0, 60.0));
+    assert_eq!(view.hovered(), None);
+    assert_eq!(view.snap(samples.iter().cloned()), None);
+}